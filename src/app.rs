@@ -1,6 +1,6 @@
-use std::cmp::min;
+use std::{cell::OnceCell, cmp::min, rc::Rc};
 
-use crate::utils::{load_watch_list, save_watch_list, DisplayType};
+use crate::utils::{load_watch_list, save_watch_list, DisplayType, IsaConfig};
 use color_eyre::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::{
@@ -13,12 +13,21 @@ use ratatui::{
 use tui_input::{backend::crossterm::EventHandler, Input};
 use vcd::ScopeItem;
 
-use crate::{snapshots::Snapshots, structures::Structures};
+use crate::{
+    lifetime::Lifetime,
+    model::Model,
+    program::MemoryImage,
+    retire::{load_reference, Commit, RetireLog, Retired},
+    snapshots::Snapshots,
+    structures::Structures,
+};
 
 #[derive(Clone, Copy, Debug)]
 enum PopupType {
     Search,
+    Table,
     Help,
+    Lifetime,
 }
 
 pub struct App {
@@ -35,6 +44,14 @@ pub struct App {
     cycle_jump: usize,
     watch_list_state: ListState,
     error_message: Option<String>,
+    /// like `error_message`, but for good news
+    info_message: Option<String>,
+    lifetime: Option<Lifetime>,
+    lifetime_list_state: ListState,
+    /// snapshot the retirement stream first diverged from the reference log in
+    divergence: Option<usize>,
+    /// what the first core retired, extracted once for the checks and the views that need it
+    retire_log: Rc<OnceCell<Option<RetireLog>>>,
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
@@ -48,9 +65,26 @@ fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
 
 impl App {
     /// Construct a new instance of [`App`].
-    pub fn new(filename: &str, start_clock: usize, debugging_length: usize) -> Self {
-        let snapshots = Snapshots::new(filename, start_clock, debugging_length).unwrap();
-        let structures = Structures::new(&snapshots);
+    pub fn new(
+        filename: &str,
+        start_clock: usize,
+        debugging_length: usize,
+        isa: IsaConfig,
+        reference: Option<String>,
+        program: Option<String>,
+    ) -> Self {
+        let mut snapshots = Snapshots::new(filename, start_clock, debugging_length).unwrap();
+        snapshots.context.isa = isa;
+        let program = program.map(|path| MemoryImage::load(&path));
+        if let Some(Ok(program)) = &program {
+            snapshots.context.symbols = program.symbols.clone();
+        }
+        let retire_log = Rc::new(OnceCell::new());
+        let structures = Structures::new(
+            &snapshots,
+            program.as_ref().and_then(|p| p.as_ref().ok()),
+            retire_log.clone(),
+        );
         let search_query = snapshots.get_base() + ".";
         let search_matches = snapshots.autocomplete_var(&search_query);
 
@@ -67,9 +101,23 @@ impl App {
             cycle_jump: 1,
             watch_list_state: ListState::default(),
             error_message: None,
+            info_message: None,
+            lifetime: None,
+            lifetime_list_state: ListState::default(),
+            divergence: None,
+            retire_log,
         };
 
         s.load_watch_list();
+        // given both, the trace is checked against each, starting on the earlier divergence
+        if let Some(reference) = reference {
+            s.diff_reference(&reference, start_clock);
+        }
+        match program {
+            Some(Ok(program)) => s.check_program(program, start_clock),
+            Some(Err(e)) => s.report_error(format!("Error loading program: {e}")),
+            None => {}
+        }
 
         s
     }
@@ -108,7 +156,12 @@ impl App {
         for (name, display_type) in self.watch_list.iter() {
             if let Some(value) = self.snapshots.get_var(name) {
                 lines.push(
-                    Text::from(format!("{}: {}\n", name, value.format(display_type))).centered(),
+                    Text::from(format!(
+                        "{}: {}\n",
+                        name,
+                        value.format(display_type, &self.snapshots.context)
+                    ))
+                    .centered(),
                 );
             } else {
                 lines.push(Text::from(format!("{name} not found!\n")).dim().centered());
@@ -129,6 +182,8 @@ impl App {
     fn draw(&mut self, frame: &mut Frame) {
         let title = if let Some(error) = &self.error_message {
             Line::from(error.clone()).bold().red().centered()
+        } else if let Some(info) = &self.info_message {
+            Line::from(info.clone()).bold().green().centered()
         } else {
             Line::from("o3o Debugger").bold().blue().centered()
         };
@@ -174,16 +229,26 @@ impl App {
 
         // render both halves (watch list and structures)
         self.render_watch_list(frame, top_half);
-        frame.render_stateful_widget(self.structures.clone(), bottom_half, &mut self.snapshots);
+        self.structures
+            .render(bottom_half, frame.buffer_mut(), &mut self.snapshots);
 
         if let Some(popup_type) = self.show_popup {
-            let area = popup_area(frame.area(), 60, 20);
+            let area = match popup_type {
+                PopupType::Help => popup_area(frame.area(), 60, 50),
+                PopupType::Lifetime => popup_area(frame.area(), 60, 40),
+                _ => popup_area(frame.area(), 60, 20),
+            };
 
             frame.render_widget(Clear, area); //this clears out the background
 
             match popup_type {
-                PopupType::Search => {
-                    let block = Block::bordered().title("Watch Variable...");
+                PopupType::Search | PopupType::Table => {
+                    let title = if matches!(popup_type, PopupType::Table) {
+                        "Show Scope as Table..."
+                    } else {
+                        "Watch Variable..."
+                    };
+                    let block = Block::bordered().title(title);
                     let search = Line::from(self.search_input.value());
                     let list = List::new(self.search_matches.clone())
                         .highlight_style(Style::new().bg(Color::Blue));
@@ -227,12 +292,261 @@ impl App {
                             " Go To End\n".into(),
                         ]),
                         Line::from(vec!["<Tab>".blue().bold(), " Switch View\n".into()]),
+                        Line::from(vec![
+                            "<[/]>".blue().bold(),
+                            " Switch between instances of the structures in a view\n".into(),
+                        ]),
+                        Line::from(vec![
+                            "<t>".blue().bold(),
+                            " Show scope with indexed entries as a table\n".into(),
+                        ]),
+                        Line::from(vec![
+                            "<J/K>".blue().bold(),
+                            " Select ROB/RS entry / ".into(),
+                            "<F>".blue().bold(),
+                            " Focus next structure in the view\n".into(),
+                        ]),
+                        Line::from(vec![
+                            "<i>".blue().bold(),
+                            " Follow selected instruction through the pipeline\n".into(),
+                        ]),
+                        Line::from(vec![
+                            "<D>".blue().bold(),
+                            " Jump to where retirement diverged from --reference/--program\n"
+                                .into(),
+                        ]),
+                        Line::from(vec![
+                            "<A>".blue().bold(),
+                            " Toggle ABI register names / ".into(),
+                            "<P>".blue().bold(),
+                            " Toggle pseudo-instructions\n".into(),
+                        ]),
                         Line::from(vec!["<q>".blue().bold(), " Quit ".into()]),
                     ])
                     .block(Block::bordered().title("Keybindings"));
 
                     frame.render_widget(instructions, area);
                 }
+                PopupType::Lifetime => self.render_lifetime(frame, area),
+            }
+        }
+    }
+
+    fn render_lifetime(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(lifetime) = &self.lifetime else {
+            return;
+        };
+
+        let title = format!(
+            "ROB #{} - {} @ cycle {}",
+            lifetime.inst.rob_num, lifetime.disasm, lifetime.cycle
+        );
+        let instructions = Line::from(vec![
+            " Jump to stage ".into(),
+            "<Enter>".blue().bold(),
+            " Close ".into(),
+            "<Esc> ".blue().bold(),
+        ])
+        .centered();
+        let block = Block::bordered()
+            .title(Line::from(title).bold().centered())
+            .title_bottom(instructions);
+
+        let mut lines: Vec<Line> = lifetime
+            .spans
+            .iter()
+            .map(|span| {
+                let cycles = span.leave_clock.saturating_sub(span.enter_clock) + 1;
+                Line::from(vec![
+                    format!("{:<6}", span.stage.name()).blue().bold(),
+                    format!(" cycle {:>6}", span.enter_clock).into(),
+                    format!(" to {:>6}", span.leave_clock).into(),
+                    format!(" ({cycles} cycles)").dim(),
+                ])
+            })
+            .collect();
+
+        lines.push(match lifetime.retired {
+            Some(cycle) => Line::from(vec![
+                format!("{:<6}", "Retire").green().bold(),
+                format!(" cycle {cycle:>6}").into(),
+            ]),
+            None => Line::from("Not retired before the end of the trace").dim(),
+        });
+
+        let list = List::new(lines).highlight_style(Style::new().bg(Color::Blue));
+        frame.render_stateful_widget(list.block(block), area, &mut self.lifetime_list_state);
+    }
+
+    fn show_lifetime(&mut self) {
+        let Some((base, rob_num, pc)) = self.structures.selected_inst(&self.snapshots) else {
+            self.error_message = Some(String::from(
+                "No instruction selected! Select a ROB or RS entry with <J/K>",
+            ));
+            return;
+        };
+
+        match Lifetime::track(&base, rob_num, pc, &mut self.snapshots) {
+            Ok(lifetime) => {
+                self.lifetime = Some(lifetime);
+                self.lifetime_list_state.select(Some(0));
+                self.show_popup = Some(PopupType::Lifetime);
+            }
+            Err(e) => self.error_message = Some(e),
+        }
+    }
+
+    fn on_lifetime_key_event(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('i') | KeyCode::Char('q') => self.show_popup = None,
+            KeyCode::Down | KeyCode::Char('j') => self.lifetime_list_state.select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.lifetime_list_state.select_previous(),
+            KeyCode::Enter => {
+                let Some(lifetime) = &self.lifetime else {
+                    return;
+                };
+                let selected = self.lifetime_list_state.selected().unwrap_or(0);
+                let index = match (lifetime.spans.get(selected), lifetime.retired) {
+                    (Some(span), _) => lifetime.index_of(span.enter),
+                    // the retire line
+                    (None, Some(_)) => lifetime.index_of(lifetime.spans.last().unwrap().leave + 1),
+                    (None, None) => return,
+                };
+                self.snapshots.go_to(index);
+                self.show_popup = None;
+            }
+            _ => {}
+        }
+    }
+
+    /// Compares what retired in the trace with a reference writeback/commit log, and jumps to the
+    /// cycle the first mismatching instruction retired in. If the trace was loaded from
+    /// `start_clock` on, it's lined up with wherever its first instructions are in the log.
+    fn diff_reference(&mut self, path: &str, start_clock: usize) {
+        let reference = match load_reference(path) {
+            Ok(reference) => reference,
+            Err(e) => {
+                self.report_error(format!("Error loading reference: {e}"));
+                return;
+            }
+        };
+        let retire_log = self.retire_log.clone();
+        let Some(log) = retire_log
+            .get_or_init(|| RetireLog::extract(&mut self.snapshots))
+            .as_ref()
+        else {
+            self.report_error(String::from(
+                "Couldn't find a ROB or committed_insts to compare with the reference!",
+            ));
+            return;
+        };
+        if let Some(skipped) = self.find_trace_start(log, &reference, start_clock, "reference") {
+            self.compare_retirement(log, &reference, skipped, "reference");
+        }
+    }
+
+    /// Runs the program through the reference model in lockstep with what retired in the trace,
+    /// and jumps to the first instruction where they disagree.
+    fn check_program(&mut self, memory: MemoryImage, start_clock: usize) {
+        let retire_log = self.retire_log.clone();
+        let Some(log) = retire_log
+            .get_or_init(|| RetireLog::extract(&mut self.snapshots))
+            .as_ref()
+        else {
+            self.report_error(String::from(
+                "Couldn't find a ROB or committed_insts to check against the program!",
+            ));
+            return;
+        };
+        // the model starts from reset, so it also runs everything that could have retired before
+        // the trace starts: no more per clock than the trace ever retires in one
+        let per_clock = log
+            .entries
+            .chunk_by(|a, b| a.cycle == b.cycle)
+            .map(<[Retired]>::len)
+            .max()
+            .unwrap_or(1);
+        let (reference, halt) = Model::run(
+            memory,
+            self.snapshots.context.isa,
+            start_clock * per_clock + log.entries.len(),
+        );
+        let against = match halt {
+            Some(halt) => format!("program (the model {halt})"),
+            None => String::from("program"),
+        };
+        if let Some(skipped) = self.find_trace_start(log, &reference, start_clock, &against) {
+            self.compare_retirement(log, &reference, skipped, &against);
+        }
+    }
+
+    /// How many of `reference`'s commits came before the trace, which was loaded from
+    /// `start_clock` on. Shows an error if its start can't be found in `reference`.
+    fn find_trace_start(
+        &mut self,
+        log: &RetireLog,
+        reference: &[Commit],
+        start_clock: usize,
+        against: &str,
+    ) -> Option<usize> {
+        if start_clock == 0 {
+            return Some(0);
+        }
+        let skipped = log.offset_in(reference);
+        if skipped.is_none() {
+            self.report_error(format!(
+                "Couldn't find where the trace starts (clock {start_clock}) in the {against}, \
+                 load it from the start to compare"
+            ));
+        }
+        skipped
+    }
+
+    /// `skipped` is how many of `reference`'s commits came before the trace started. If the trace
+    /// was already compared with something else, the cursor goes to the earlier divergence.
+    fn compare_retirement(
+        &mut self,
+        log: &RetireLog,
+        reference: &[Commit],
+        skipped: usize,
+        against: &str,
+    ) {
+        match log.diff(reference, skipped) {
+            Some(divergence) => {
+                let index = self.snapshots.cycle_indices()[divergence.actual.cycle];
+                self.divergence = Some(self.divergence.map_or(index, |other| other.min(index)));
+                self.report_error(format!("{divergence} ({against})"));
+                self.jump_to_divergence();
+            }
+            None => {
+                let message = format!(
+                    "All {} retired instructions match the {against} ({} commits)",
+                    log.entries.len(),
+                    reference.len() - skipped
+                );
+                self.info_message = Some(match self.info_message.take() {
+                    Some(earlier) => format!("{earlier}; {message}"),
+                    None => message,
+                });
+            }
+        }
+    }
+
+    /// Shows `message` in the title bar, after any error that's already there.
+    fn report_error(&mut self, message: String) {
+        self.error_message = Some(match self.error_message.take() {
+            Some(earlier) => format!("{earlier}; {message}"),
+            None => message,
+        });
+    }
+
+    fn jump_to_divergence(&mut self) {
+        match self.divergence {
+            Some(index) => self.snapshots.go_to(index),
+            None => {
+                self.error_message = Some(String::from(
+                    "No divergence from the --reference log or --program",
+                ))
             }
         }
     }
@@ -255,13 +569,18 @@ impl App {
     /// Handles the key events and updates the state of [`App`].
     fn on_key_event(&mut self, key: KeyEvent) {
         self.error_message = None;
-        if matches!(self.show_popup, Some(PopupType::Search)) {
+        self.info_message = None;
+        if let Some(popup_type @ (PopupType::Search | PopupType::Table)) = self.show_popup {
             match (key.modifiers, key.code) {
                 (_, KeyCode::Esc | KeyCode::Char('/')) => {
                     self.show_popup = None;
                 }
                 (_, KeyCode::Enter) => {
-                    self.handle_search_enter();
+                    if matches!(popup_type, PopupType::Table) {
+                        self.handle_table_enter();
+                    } else {
+                        self.handle_search_enter();
+                    }
                 }
                 (_, KeyCode::Up | KeyCode::BackTab) => {
                     if let Some(selected) = self.search_list_state.selected() {
@@ -284,14 +603,21 @@ impl App {
                 _ => {
                     if self.search_input.handle_event(&Event::Key(key)).is_some() {
                         self.search_query = self.search_input.value().to_owned();
-                        self.search_matches =
-                            self.snapshots.autocomplete_var(self.search_input.value());
+                        self.search_matches = if matches!(popup_type, PopupType::Table) {
+                            self.snapshots.autocomplete_table(self.search_input.value())
+                        } else {
+                            self.snapshots.autocomplete_var(self.search_input.value())
+                        };
                         self.search_list_state.select(None);
                     }
                 }
             }
             return;
         }
+        if let Some(PopupType::Lifetime) = self.show_popup {
+            self.on_lifetime_key_event(key);
+            return;
+        }
 
         match (key.modifiers, key.code) {
             (_, KeyCode::Char('q'))
@@ -321,7 +647,36 @@ impl App {
                     self.show_popup = Some(PopupType::Help)
                 }
             }
-            (_, KeyCode::Char('/')) => self.show_popup = Some(PopupType::Search),
+            (_, KeyCode::Char('/')) => {
+                self.search_matches = self.snapshots.autocomplete_var(self.search_input.value());
+                self.search_list_state.select(None);
+                self.show_popup = Some(PopupType::Search);
+            }
+            (_, KeyCode::Char('t')) => {
+                self.search_matches = self.snapshots.autocomplete_table(self.search_input.value());
+                self.search_list_state.select(None);
+                self.show_popup = Some(PopupType::Table);
+            }
+            (_, KeyCode::Char('i')) => self.show_lifetime(),
+            (_, KeyCode::Char('D')) => self.jump_to_divergence(),
+            (_, KeyCode::Char('A')) => {
+                let context = &mut self.snapshots.context;
+                context.abi_registers = !context.abi_registers;
+                let on = context.abi_registers;
+                self.info_message = Some(format!(
+                    "Registers shown by {} name",
+                    if on { "ABI" } else { "number" }
+                ));
+            }
+            (_, KeyCode::Char('P')) => {
+                let context = &mut self.snapshots.context;
+                context.pseudo_insts = !context.pseudo_insts;
+                let on = context.pseudo_insts;
+                self.info_message = Some(format!(
+                    "Pseudo-instructions {}",
+                    if on { "on" } else { "off" }
+                ));
+            }
 
             // vim bindings
             (_, KeyCode::Left | KeyCode::Char('h')) => self.handle_left_key(),
@@ -330,7 +685,11 @@ impl App {
             (_, KeyCode::Right | KeyCode::Char('l')) => self.handle_right_key(),
             (_, KeyCode::Char('G')) => self.select_last(),
             _ => {
-                self.structures.on_key_event(key);
+                if let Some(cycle) = self.structures.on_key_event(key) {
+                    if let Some(&index) = self.snapshots.cycle_indices().get(cycle) {
+                        self.snapshots.go_to(index);
+                    }
+                }
             }
         }
     }
@@ -380,6 +739,21 @@ impl App {
         self.show_popup = None;
     }
 
+    fn handle_table_enter(&mut self) {
+        let value = if let Some(index) = self.search_list_state.selected() {
+            self.search_matches[index].clone()
+        } else {
+            self.search_input.value().trim().to_owned()
+        };
+
+        if !self.structures.show_table(&value, &self.snapshots) {
+            self.error_message = Some(format!("{value} has no indexed sub-scopes!"));
+            return;
+        }
+        self.search_input = Input::new(self.snapshots.get_base() + ".");
+        self.show_popup = None;
+    }
+
     fn change_selected_watch_mode(&mut self) {
         let Some(index) = self.watch_list_state.selected() else {
             return;
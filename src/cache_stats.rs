@@ -0,0 +1,243 @@
+//! Where in a cache hits, misses, evictions and writebacks happened over the course of the trace.
+use crate::{
+    snapshots::{Snapshots, VerilogValue},
+    utils::{get_known, indexed},
+};
+
+/// Width of the offset within a block when the cache doesn't show its address layout, i.e.
+/// 8-byte blocks. Addresses are `tag ++ set ++ offset`.
+const DEFAULT_BLOCK_OFFSET_BITS: usize = 3;
+
+/// Width of the `block_offset` field of the cache at `base`'s addresses, taken from its query or
+/// MSHR addresses.
+fn block_offset_bits(base: &str, snapshots: &Snapshots) -> usize {
+    [
+        format!("{base}.query_addr.block_offset"),
+        format!("{base}.waiting_commands[0].addr.block_offset"),
+    ]
+    .iter()
+    .find_map(|key| match snapshots.get_var(key)? {
+        VerilogValue::Vector(bits) => Some(bits.len()),
+        VerilogValue::Scalar(_) => Some(1),
+        VerilogValue::Missing => None,
+    })
+    .unwrap_or(DEFAULT_BLOCK_OFFSET_BITS)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventKind {
+    /// a query answered the cycle it was made, from this line
+    Hit,
+    /// a line filled with a new block, i.e. the end of a miss
+    Miss,
+    /// a valid line replaced by a fill
+    Eviction,
+    /// a dirty line replaced or invalidated
+    Writeback,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 4] = [
+        EventKind::Hit,
+        EventKind::Miss,
+        EventKind::Eviction,
+        EventKind::Writeback,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EventKind::Hit => "hits",
+            EventKind::Miss => "misses",
+            EventKind::Eviction => "evictions",
+            EventKind::Writeback => "writebacks",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct CacheEvent {
+    /// as a position in [`Snapshots::cycle_indices`]
+    pub cycle: usize,
+    pub set: usize,
+    pub way: usize,
+    pub kind: EventKind,
+}
+
+/// What a line held in some cycle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Line {
+    valid: bool,
+    tag: usize,
+    dirty: bool,
+}
+
+impl Line {
+    fn read(base: &str, snapshots: &Snapshots) -> Self {
+        let high = |key: &str| {
+            snapshots
+                .get_var(&format!("{base}.{key}"))
+                .is_some_and(VerilogValue::is_high)
+        };
+        Self {
+            valid: high("valid"),
+            tag: get_known(&format!("{base}.tag"), snapshots).unwrap_or(0),
+            dirty: high("dirty"),
+        }
+    }
+}
+
+/// Every hit, miss, eviction and writeback of one cache, by set and way.
+pub struct CacheHistory {
+    pub num_sets: usize,
+    pub num_ways: usize,
+    /// in the order they happened
+    pub events: Vec<CacheEvent>,
+    /// hits that no line's tag matched, e.g. ones forwarded from an MSHR
+    pub unplaced_hits: usize,
+    /// clock count of each cycle
+    pub clocks: Vec<usize>,
+}
+
+impl CacheHistory {
+    /// Metadata of every line of the cache at `base` as `lines[set][way]`: `metadata[set][way]`
+    /// in set-associative caches, `metadata[way]` in fully associative ones and `metadata[set]`
+    /// in direct-mapped ones.
+    pub fn lines(base: &str, snapshots: &Snapshots) -> Vec<Vec<String>> {
+        let metadata = format!("{base}.metadata");
+        if snapshots.get_scope(&format!("{metadata}[0][0]")).is_some() {
+            let num_ways = indexed(&format!("{metadata}[0]"), snapshots).len();
+            let mut lines = Vec::new();
+            while snapshots
+                .get_scope(&format!("{metadata}[{}][0]", lines.len()))
+                .is_some()
+            {
+                let set = format!("{metadata}[{}]", lines.len());
+                lines.push((0..num_ways).map(|way| format!("{set}[{way}]")).collect());
+            }
+            return lines;
+        }
+        let entries = indexed(&metadata, snapshots);
+        if snapshots
+            .get_var(&format!("{base}.dbg_this_is_facache"))
+            .is_some()
+        {
+            vec![entries]
+        } else {
+            entries.into_iter().map(|line| vec![line]).collect()
+        }
+    }
+
+    /// Goes through the whole trace watching the cache at `base`'s queries and metadata.
+    pub fn extract(base: &str, snapshots: &mut Snapshots) -> Self {
+        let lines = Self::lines(base, snapshots);
+        let num_sets = lines.len();
+        let num_ways = lines.first().map_or(0, Vec::len);
+        // addresses can have more tag bits than the design keeps
+        let tag_width = match lines
+            .first()
+            .and_then(|ways| ways.first())
+            .and_then(|line| snapshots.get_var(&format!("{line}.tag")))
+        {
+            Some(VerilogValue::Vector(bits)) => bits.len() as u32,
+            _ => usize::BITS,
+        };
+        let tag_mask = usize::MAX.checked_shr(usize::BITS - tag_width).unwrap_or(0);
+        let offset_bits = block_offset_bits(base, snapshots);
+
+        let mut events = Vec::new();
+        let mut unplaced_hits = 0;
+        let mut clocks = Vec::new();
+
+        let cycle_indices = snapshots.cycle_indices();
+        let original = snapshots.index();
+        let mut previous: Option<Vec<Vec<Line>>> = None;
+        // (command, addr) of the query that missed and hasn't been answered yet
+        let mut pending = None;
+        for (cycle, &index) in cycle_indices.iter().enumerate() {
+            snapshots.go_to(index);
+            clocks.push(snapshots.get_clock_count());
+            let mut push = |set, way, kind| {
+                events.push(CacheEvent {
+                    cycle,
+                    set,
+                    way,
+                    kind,
+                })
+            };
+
+            let current: Vec<Vec<Line>> = lines
+                .iter()
+                .map(|set| set.iter().map(|line| Line::read(line, snapshots)).collect())
+                .collect();
+
+            // hits are looked up in the lines as they were before this cycle's fills
+            let before = previous.as_ref().unwrap_or(&current);
+            let command = get_known(&format!("{base}.query_command"), snapshots);
+            let addr = get_known(&format!("{base}.query_addr"), snapshots);
+            let answered = snapshots
+                .get_var(&format!("{base}.valid"))
+                .is_some_and(VerilogValue::is_high);
+            match command.filter(|&command| command != 0).zip(addr) {
+                Some(query) if pending != Some(query) && answered => {
+                    let (_, addr) = query;
+                    let block = addr >> offset_bits;
+                    let set = block % num_sets.max(1);
+                    let tag = (block / num_sets.max(1)) & tag_mask;
+                    let way = before.get(set).and_then(|ways| {
+                        ways.iter().position(|line| line.valid && line.tag == tag)
+                    });
+                    match way {
+                        Some(way) => push(set, way, EventKind::Hit),
+                        None => unplaced_hits += 1,
+                    }
+                    pending = None;
+                }
+                Some(query) => pending = (!answered).then_some(query),
+                None => pending = None,
+            }
+
+            if let Some(before) = previous.as_ref() {
+                for (set, (old_ways, new_ways)) in before.iter().zip(current.iter()).enumerate() {
+                    for (way, (old, new)) in old_ways.iter().zip(new_ways.iter()).enumerate() {
+                        let replaced = old.valid && (!new.valid || new.tag != old.tag);
+                        if new.valid && (!old.valid || new.tag != old.tag) {
+                            push(set, way, EventKind::Miss);
+                            if old.valid {
+                                push(set, way, EventKind::Eviction);
+                            }
+                        }
+                        if replaced && old.dirty {
+                            push(set, way, EventKind::Writeback);
+                        }
+                    }
+                }
+            }
+            previous = Some(current);
+        }
+        snapshots.go_to(original);
+
+        Self {
+            num_sets,
+            num_ways,
+            events,
+            unplaced_hits,
+            clocks,
+        }
+    }
+
+    /// Number of `kind` events in each line, as `counts[set][way]`.
+    pub fn tally(&self, kind: EventKind) -> Vec<Vec<usize>> {
+        let mut counts = vec![vec![0; self.num_ways]; self.num_sets];
+        for event in self.events.iter().filter(|event| event.kind == kind) {
+            counts[event.set][event.way] += 1;
+        }
+        counts
+    }
+
+    pub fn count(&self, kind: EventKind) -> usize {
+        self.events
+            .iter()
+            .filter(|event| event.kind == kind)
+            .count()
+    }
+}
@@ -2,7 +2,10 @@
  * HEADERS variables that specify the columns to display for different
  * structs and how to fetch/format them.
  */
-use crate::utils::{parse_mem_size, Column, DisplayType};
+use crate::{
+    snapshots::VerilogValue,
+    utils::{parse_mem_size, Column, DisplayType},
+};
 
 /*
 typedef struct packed {
@@ -120,8 +123,8 @@ pub const MEM_INPUT_HEADERS: [Column; 9] = [
     Column {
         name: "addr",
         key: Some("addr"),
-        width: 8,
-        display_type: DisplayType::Hex,
+        width: 16,
+        display_type: DisplayType::Custom(VerilogValue::as_addr),
     },
     Column {
         name: "data",
@@ -235,14 +238,14 @@ pub const BRANCH_OUTPUT_HEADERS: [Column; 7] = [
     Column {
         name: "pc",
         key: Some("pc"),
-        width: 6,
-        display_type: DisplayType::Hex,
+        width: 14,
+        display_type: DisplayType::Custom(VerilogValue::as_addr),
     },
     Column {
         name: "target_pc",
-        key: Some("pc"),
-        width: 9,
-        display_type: DisplayType::Hex,
+        key: Some("target_pc"),
+        width: 14,
+        display_type: DisplayType::Custom(VerilogValue::as_addr),
     },
     Column {
         name: "bid",
@@ -307,7 +310,7 @@ pub const MSHR_HEADERS: [Column; 7] = [
         name: "size",
         key: Some("size"),
         width: 6,
-        display_type: DisplayType::Custom(parse_mem_size),
+        display_type: DisplayType::Custom(|size, _| parse_mem_size(size)),
     },
     Column {
         name: "is_store",
@@ -0,0 +1,351 @@
+//! Consistency checks on the core's structures, run on every cycle of the trace. A broken design
+//! usually gets into one of these impossible states well before it produces a wrong result.
+use std::{collections::HashMap, ops::Range};
+
+use vcd::ScopeItem;
+
+use crate::{
+    snapshots::{Snapshots, VerilogValue},
+    structures::nearest_marker,
+    utils::{get_known, indexed},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Check {
+    /// the ROB's head plus its size is its tail, and all three are in range
+    RobPointers,
+    /// no physical register is mapped to by two architectural ones
+    DuplicateTags,
+    /// nothing in a ROB, RS or store queue entry that's in use is x
+    UnknownInValid,
+    /// every bit set in a ROB, RS or store queue entry's bmask is a branch still in the branch stack
+    StaleBmask,
+}
+
+impl Check {
+    pub const ALL: [Check; 4] = [
+        Check::RobPointers,
+        Check::DuplicateTags,
+        Check::UnknownInValid,
+        Check::StaleBmask,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Check::RobPointers => "ROB pointers",
+            Check::DuplicateTags => "duplicate tags",
+            Check::UnknownInValid => "x in valid entry",
+            Check::StaleBmask => "stale bmask",
+        }
+    }
+}
+
+/// One check failing the same way over consecutive cycles.
+#[derive(Clone, Debug)]
+pub struct Violation {
+    pub check: Check,
+    /// what was wrong, e.g. which entry and field
+    pub detail: String,
+    /// as positions in [`Snapshots::cycle_indices`]
+    pub cycles: Range<usize>,
+    /// clock count of the first cycle
+    pub clock: usize,
+}
+
+/// How to tell which entries of a structure are in use.
+enum Liveness {
+    /// the ones from `head` on, `size` of them, wrapping around
+    Queue,
+    /// the ones with a functional unit, like the RS view
+    Fu,
+}
+
+/// A structure whose entries are only in use some of the time.
+struct Entries {
+    /// last part of the structure's path, for messages
+    name: String,
+    entries: Vec<String>,
+    head: String,
+    size: String,
+    liveness: Liveness,
+}
+
+impl Entries {
+    fn new(base: &str, liveness: Liveness, snapshots: &Snapshots) -> Self {
+        Self {
+            name: base.rsplit('.').next().unwrap_or(base).to_owned(),
+            entries: indexed(&format!("{base}.entries"), snapshots),
+            head: format!("{base}.head"),
+            size: format!("{base}.size"),
+            liveness,
+        }
+    }
+
+    /// Indices of the entries in use this cycle.
+    fn live(&self, snapshots: &Snapshots) -> Vec<usize> {
+        let n = self.entries.len();
+        match self.liveness {
+            Liveness::Queue => {
+                match (
+                    get_known(&self.head, snapshots),
+                    get_known(&self.size, snapshots),
+                ) {
+                    (Some(head), Some(size)) => (0..size.min(n)).map(|k| (head + k) % n).collect(),
+                    _ => Vec::new(),
+                }
+            }
+            Liveness::Fu => (0..n)
+                .filter(|&i| {
+                    snapshots
+                        .get_var(&format!("{}.fu", self.entries[i]))
+                        .is_some_and(|fu| !fu.is_low() && !fu.is_unknown())
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Every signal under the scope at `path` that's x, relative to it.
+fn unknown_vars(path: &str, snapshots: &Snapshots) -> Vec<String> {
+    fn walk(path: &str, prefix: &str, snapshots: &Snapshots, unknown: &mut Vec<String>) {
+        let Some(scope) = snapshots.get_scope(path) else {
+            return;
+        };
+        for item in scope.items.iter() {
+            match item {
+                ScopeItem::Var(var) => {
+                    let name = format!("{path}.{}", var.reference);
+                    if snapshots
+                        .get_var(&name)
+                        .is_some_and(VerilogValue::is_unknown)
+                    {
+                        unknown.push(format!("{prefix}{}", var.reference));
+                    }
+                }
+                ScopeItem::Scope(sub) => walk(
+                    &format!("{path}.{}", sub.identifier),
+                    &format!("{prefix}{}.", sub.identifier),
+                    snapshots,
+                    unknown,
+                ),
+                _ => {}
+            }
+        }
+    }
+    let mut unknown = Vec::new();
+    walk(path, "", snapshots, &mut unknown);
+    unknown
+}
+
+/// What's wrong with the ROB at `base`'s pointers, if anything.
+fn check_rob_pointers(base: &str, num_entries: usize, snapshots: &Snapshots) -> Option<String> {
+    let get = |name: &str| get_known(&format!("{base}.{name}"), snapshots);
+    let (Some(head), Some(tail), Some(size)) = (get("head"), get("tail"), get("size")) else {
+        let unknown: Vec<&str> = ["head", "tail", "size"]
+            .into_iter()
+            .filter(|name| get(name).is_none())
+            .collect();
+        return Some(format!("{} is x", unknown.join(", ")));
+    };
+    if head >= num_entries || tail >= num_entries {
+        Some(format!(
+            "head {head} or tail {tail} is past the last of {num_entries} entries"
+        ))
+    } else if size > num_entries {
+        Some(format!(
+            "size {size} is more than the {num_entries} entries"
+        ))
+    } else if (head + size) % num_entries != tail {
+        Some(format!("head {head} + size {size} isn't tail {tail}"))
+    } else {
+        None
+    }
+}
+
+/// Physical registers mapped to by more than one architectural register in the map table array at
+/// `table`.
+fn check_duplicate_tags(table: &str, entries: &[String], snapshots: &Snapshots) -> Vec<String> {
+    let name = table.rsplit('.').next().unwrap_or(table);
+    let mut mapped: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (arch_reg, entry) in entries.iter().enumerate() {
+        if let Some(phys_reg) = get_known(&format!("{entry}.value"), snapshots) {
+            mapped.entry(phys_reg).or_default().push(arch_reg);
+        }
+    }
+    let mut duplicates: Vec<(usize, Vec<usize>)> = mapped
+        .into_iter()
+        .filter(|(_, arch_regs)| arch_regs.len() > 1)
+        .collect();
+    duplicates.sort();
+    duplicates
+        .into_iter()
+        .map(|(phys_reg, arch_regs)| {
+            let arch_regs: Vec<String> = arch_regs.iter().map(|r| format!("x{r}")).collect();
+            format!("{name}: p{phys_reg} is mapped by {}", arch_regs.join(", "))
+        })
+        .collect()
+}
+
+/// Everything that failed a check over the trace.
+pub struct InvariantLog {
+    /// in the order they started
+    pub violations: Vec<Violation>,
+    /// which of [`Check::ALL`] the design has the structures for
+    pub checked: [bool; Check::ALL.len()],
+}
+
+impl InvariantLog {
+    /// Checks the core the structure at `base` is in on every cycle out of reset.
+    pub fn collect(base: &str, snapshots: &mut Snapshots) -> Self {
+        let header = &snapshots.header;
+        let rob = nearest_marker(header, "rob", base);
+        let near = rob.clone().unwrap_or_else(|| base.to_owned());
+        let rs = nearest_marker(header, "rs", &near);
+        let sq = nearest_marker(header, "store_queue", &near)
+            .filter(|sq| snapshots.get_var(&format!("{sq}.size")).is_some());
+        let map_table = nearest_marker(header, "map_table", &near);
+        // a branch's bit in the bmasks is its entry's index in the branch stack
+        let branches = nearest_marker(header, "bstack", &near)
+            .map(|bstack| indexed(&format!("{bstack}.entries"), snapshots))
+            .unwrap_or_default();
+
+        let mut structures = Vec::new();
+        if let Some(rob) = &rob {
+            structures.push(Entries::new(rob, Liveness::Queue, snapshots));
+        }
+        if let Some(rs) = &rs {
+            structures.push(Entries::new(rs, Liveness::Fu, snapshots));
+        }
+        if let Some(sq) = &sq {
+            structures.push(Entries::new(sq, Liveness::Queue, snapshots));
+        }
+        let rob_size = structures.first().map_or(0, |rob| rob.entries.len());
+        let tables: Vec<(String, Vec<String>)> = map_table
+            .iter()
+            .flat_map(|base| {
+                [
+                    format!("{base}.map_table"),
+                    format!("{base}.arch_map_table"),
+                ]
+            })
+            .map(|table| {
+                let entries = indexed(&table, snapshots);
+                (table, entries)
+            })
+            .filter(|(_, entries)| !entries.is_empty())
+            .collect();
+
+        let checked = [
+            rob.is_some() && rob_size > 0,
+            !tables.is_empty(),
+            !structures.is_empty(),
+            !branches.is_empty() && !structures.is_empty(),
+        ];
+
+        let mut violations: Vec<Violation> = Vec::new();
+        // violations that failed last cycle, to extend if they fail the same way again
+        let mut open: HashMap<(Check, String), usize> = HashMap::new();
+
+        let reset = format!("{}.reset", snapshots.get_base());
+        let original = snapshots.index();
+        for (cycle, index) in snapshots.cycle_indices().into_iter().enumerate() {
+            snapshots.go_to(index);
+            if snapshots.get_var(&reset).is_some_and(VerilogValue::is_high) {
+                continue;
+            }
+
+            let mut failures = Vec::new();
+            if let Some(rob) = rob.as_ref().filter(|_| rob_size > 0) {
+                if let Some(detail) = check_rob_pointers(rob, rob_size, snapshots) {
+                    failures.push((Check::RobPointers, detail));
+                }
+            }
+            for (table, entries) in tables.iter() {
+                let duplicates = check_duplicate_tags(table, entries, snapshots);
+                failures.extend(duplicates.into_iter().map(|d| (Check::DuplicateTags, d)));
+            }
+
+            // branches in the stack this cycle, the only ones instructions can depend on
+            let live_branches = branches
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| {
+                    snapshots
+                        .get_var(&format!("{entry}.valid"))
+                        .is_some_and(VerilogValue::is_high)
+                })
+                .fold(0, |mask, (i, _)| mask | 1 << i);
+            for structure in structures.iter() {
+                for i in structure.live(snapshots) {
+                    let entry = structure.entries[i].as_str();
+                    let unknown = unknown_vars(entry, snapshots);
+                    if !unknown.is_empty() {
+                        failures.push((
+                            Check::UnknownInValid,
+                            format!("{}[{i}]: {} x", structure.name, unknown.join(", ")),
+                        ));
+                    }
+
+                    let bmask = get_known(&format!("{entry}.bmask"), snapshots).unwrap_or(0);
+                    let stale = bmask & !live_branches;
+                    if !branches.is_empty() && stale != 0 {
+                        failures.push((
+                            Check::StaleBmask,
+                            format!(
+                                "{}[{i}]: bmask {bmask:b} has {stale:b}, which isn't in the branch stack",
+                                structure.name
+                            ),
+                        ));
+                    }
+                }
+            }
+
+            let clock = snapshots.get_clock_count();
+            for (check, detail) in failures {
+                let key = (check, detail);
+                match open.get(&key) {
+                    Some(&i) if violations[i].cycles.end == cycle => {
+                        violations[i].cycles.end = cycle + 1;
+                    }
+                    _ => {
+                        open.insert(key.clone(), violations.len());
+                        violations.push(Violation {
+                            check: key.0,
+                            detail: key.1,
+                            cycles: cycle..cycle + 1,
+                            clock,
+                        });
+                    }
+                }
+            }
+        }
+        snapshots.go_to(original);
+
+        Self {
+            violations,
+            checked,
+        }
+    }
+
+    /// Number of violations of `check`, counting each run of cycles once.
+    pub fn count(&self, check: Check) -> usize {
+        self.violations
+            .iter()
+            .filter(|violation| violation.check == check)
+            .count()
+    }
+
+    /// Cycle the first violation after `cycle` starts at (or the last one before it, if `forward`
+    /// is false).
+    pub fn next_violation(&self, cycle: usize, forward: bool) -> Option<usize> {
+        let mut starts = self
+            .violations
+            .iter()
+            .map(|violation| violation.cycles.start);
+        if forward {
+            starts.find(|&c| c > cycle)
+        } else {
+            starts.rev().find(|&c| c < cycle)
+        }
+    }
+}
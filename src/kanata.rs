@@ -0,0 +1,84 @@
+//! Writes reconstructed pipelines as Kanata logs, for viewing in Konata
+//! (<https://github.com/shioyadan/Konata>).
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use crate::{
+    lifetime::{Fate, Pipeline, Stage, Trace},
+    snapshots::Snapshots,
+};
+
+/// Writes every instruction that went through the first core's ROB in the loaded cycles to
+/// `path`. Returns how many instructions were written.
+pub fn export(snapshots: &mut Snapshots, path: &str) -> io::Result<usize> {
+    let pipeline = Pipeline::new(&snapshots.get_base(), snapshots).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "Couldn't find a ROB (dbg_this_is_rob) to reconstruct the pipeline from!",
+        )
+    })?;
+
+    let cycle_indices = snapshots.cycle_indices();
+    let trace = Trace::build(&pipeline, snapshots, &cycle_indices, 0..cycle_indices.len());
+
+    // events that happen at the start of each cycle
+    let mut events: Vec<Vec<String>> = vec![Vec::new(); trace.cycles.len() + 1];
+    let mut retire_id = 0;
+    let first = trace.cycles.start;
+
+    for (id, traced) in trace.insts.iter().enumerate() {
+        let start = traced.start.max(first);
+        let fate = traced.fate;
+
+        events[start - first].push(format!("I\t{id}\t{id}\t0"));
+        events[start - first].push(format!("L\t{id}\t0\t{}", traced.disasm));
+
+        let mut current: Option<char> = None;
+        for cycle in start..=traced.end() {
+            let (stage, _) = traced.stage_at(cycle).unwrap();
+            let name = if cycle == traced.end() && fate == Fate::Retired {
+                Stage::Rob.letter()
+            } else {
+                stage.letter()
+            };
+
+            if current != Some(name) {
+                if let Some(current) = current {
+                    events[cycle - first].push(format!("E\t{id}\t0\t{current}"));
+                }
+                events[cycle - first].push(format!("S\t{id}\t0\t{name}"));
+                current = Some(name);
+            }
+        }
+
+        let end = traced.end() + 1 - first;
+        if let Some(current) = current {
+            events[end].push(format!("E\t{id}\t0\t{current}"));
+        }
+        match fate {
+            Fate::Retired => {
+                events[end].push(format!("R\t{id}\t{retire_id}\t0"));
+                retire_id += 1;
+            }
+            Fate::Squashed => events[end].push(format!("R\t{id}\t0\t1")),
+            Fate::InFlight => {}
+        }
+    }
+
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "Kanata\t0004")?;
+    writeln!(file, "C=\t{}", trace.clocks.first().copied().unwrap_or(0))?;
+    for (i, cycle_events) in events.iter().enumerate() {
+        if i > 0 {
+            writeln!(file, "C\t1")?;
+        }
+        for event in cycle_events {
+            writeln!(file, "{event}")?;
+        }
+    }
+    file.flush()?;
+
+    Ok(trace.insts.len())
+}
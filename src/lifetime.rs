@@ -0,0 +1,575 @@
+use std::{collections::HashMap, ops::Range};
+
+use crate::{
+    snapshots::{Snapshots, VerilogValue},
+    structures::nearest_marker,
+    utils::{get_known, indexed, parse_opinfo},
+};
+
+/// How far before dispatch to look for the instruction in fetch, in cycles.
+const FETCH_LOOKBACK: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    Fetch,
+    Rs,
+    Issue,
+    Fu,
+    Complete,
+    Rob,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 6] = [
+        Stage::Fetch,
+        Stage::Rs,
+        Stage::Issue,
+        Stage::Fu,
+        Stage::Complete,
+        Stage::Rob,
+    ];
+
+    /// Letter used for the stage in pipeline diagrams, the last cycle in the ROB is retirement.
+    pub fn letter(&self) -> char {
+        match self {
+            Stage::Fetch => 'F',
+            Stage::Rs => 'D',
+            Stage::Issue => 'I',
+            Stage::Fu => 'X',
+            Stage::Complete => 'C',
+            Stage::Rob => 'R',
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Fetch => "Fetch",
+            Stage::Rs => "RS",
+            Stage::Issue => "Issue",
+            Stage::Fu => "FU",
+            Stage::Complete => "CDB",
+            Stage::Rob => "ROB",
+        }
+    }
+}
+
+/// One dynamic instruction: which ROB entry it's in, its PC, and the physical register it
+/// writes, which is all the CDB knows it by.
+#[derive(Clone, Copy, Debug)]
+pub struct Inst {
+    pub rob_num: usize,
+    pub pc: usize,
+    pub dest_tag: usize,
+}
+
+/// Paths of every structure an instruction passes through in one core, found from the
+/// `dbg_this_is_*` markers closest to its ROB, along with the arrays in them to look through.
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    pub rob: String,
+    pub rob_size: usize,
+    /// `incoming_instrs[]` rows of fetch and the fetch PC they start at
+    fetch: Option<(String, Vec<String>)>,
+    rs_entries: Vec<String>,
+    issue_packets: Vec<String>,
+    fu_packets: Vec<String>,
+    cdb: Vec<String>,
+}
+
+/// Where every instruction was in one cycle.
+#[derive(Clone, Debug, Default)]
+pub struct CycleState {
+    /// what's in each ROB entry, `None` if it's empty
+    pub rob: Vec<Option<Inst>>,
+    /// ROB head and how many entries are in use
+    pub rob_head: usize,
+    pub rob_count: usize,
+    /// PCs being fetched
+    pub fetch: Vec<usize>,
+    /// (rob_num, PC) of valid RS entries
+    pub rs: Vec<(usize, usize)>,
+    /// (rob_num, PC if the packet has one) of valid issue and FU output packets
+    pub issue: Vec<(usize, Option<usize>)>,
+    pub fu: Vec<(usize, Option<usize>)>,
+    /// destination tags broadcast on the CDB
+    pub cdb: Vec<usize>,
+}
+
+/// Paths of `{base}[0]`, `{base}[1]`, ..., or just `base` if it isn't an array but exists.
+fn entries(base: &str, snapshots: &Snapshots) -> Vec<String> {
+    if snapshots.get_scope(base).is_some() {
+        return vec![base.to_owned()];
+    }
+    indexed(base, snapshots)
+}
+
+fn is_valid(base: &str, snapshots: &Snapshots) -> bool {
+    snapshots
+        .get_var(&format!("{base}.valid"))
+        .is_some_and(VerilogValue::is_high)
+}
+
+impl Pipeline {
+    /// Pipeline of the core that the structure at `base` (e.g. a ROB or RS) belongs to.
+    pub fn new(base: &str, snapshots: &Snapshots) -> Option<Self> {
+        let header = &snapshots.header;
+        let rob = nearest_marker(header, "rob", base)?;
+        let rob_size = entries(&format!("{rob}.entries"), snapshots).len();
+
+        let arrays = |marker: &str, names: &[&str]| -> Vec<String> {
+            let Some(base) = nearest_marker(header, marker, &rob) else {
+                return Vec::new();
+            };
+            names
+                .iter()
+                .flat_map(|name| entries(&format!("{base}.{name}"), snapshots))
+                .collect()
+        };
+
+        let fetch = nearest_marker(header, "fetch", &rob).map(|fetch| {
+            let rows = entries(&format!("{fetch}.incoming_instrs"), snapshots);
+            (fetch, rows)
+        });
+
+        Some(Self {
+            fetch,
+            rs_entries: arrays("rs", &["entries"]),
+            issue_packets: arrays(
+                "issue",
+                &[
+                    "alu_packets",
+                    "mult_packets",
+                    "branch_packets",
+                    "store_packets",
+                    "load_packet",
+                ],
+            ),
+            fu_packets: arrays(
+                "fu",
+                &[
+                    "alu_output_packets",
+                    "mult_output_packets",
+                    "branch_output_packets",
+                    "store_output_packets",
+                ],
+            ),
+            cdb: arrays("complete", &["cdb"]),
+            rob,
+            rob_size,
+        })
+    }
+
+    /// Disassembly of the instruction in ROB entry `rob_num`, e.g. `1c: addi x8, x0, 7`.
+    pub fn rob_disasm(&self, rob_num: usize, snapshots: &Snapshots) -> String {
+        parse_opinfo(&format!("{}.entries[{rob_num}].info", self.rob), snapshots)
+    }
+
+    /// Reads where every instruction is in the current snapshot.
+    pub fn sample(&self, snapshots: &Snapshots) -> CycleState {
+        let mut state = CycleState {
+            rob: vec![None; self.rob_size],
+            ..Default::default()
+        };
+
+        let head = get_known(&format!("{}.head", self.rob), snapshots);
+        let size = get_known(&format!("{}.size", self.rob), snapshots);
+        if let (Some(head), Some(size), true) = (head, size, self.rob_size > 0) {
+            state.rob_head = head % self.rob_size;
+            state.rob_count = size.min(self.rob_size);
+
+            for offset in 0..state.rob_count {
+                let rob_num = (state.rob_head + offset) % self.rob_size;
+                let entry = format!("{}.entries[{rob_num}]", self.rob);
+                let Some(pc) = get_known(&format!("{entry}.info.PC"), snapshots) else {
+                    continue;
+                };
+                let dest_tag = get_known(&format!("{entry}.t"), snapshots).unwrap_or(0);
+                state.rob[rob_num] = Some(Inst {
+                    rob_num,
+                    pc,
+                    dest_tag,
+                });
+            }
+        }
+
+        if let Some((fetch, rows)) = &self.fetch {
+            // fall back to counting up from the fetch PC if the rows don't carry their own
+            let mut fetch_pc = get_known(&format!("{fetch}.PC"), snapshots);
+            for row in rows.iter() {
+                let pc = match snapshots.get_var(&format!("{row}.PC")) {
+                    Some(pc) => (!pc.is_unknown()).then(|| pc.as_usize()),
+                    None => fetch_pc,
+                };
+                state.fetch.extend(pc);
+                let len = get_known(&format!("{row}.inst"), snapshots)
+                    .map_or(4, |bits| snapshots.context.isa.inst_len(bits));
+                fetch_pc = fetch_pc.map(|pc| pc + len);
+            }
+        }
+
+        for row in self.rs_entries.iter() {
+            let fu = snapshots.get_var(&format!("{row}.fu"));
+            if !fu.is_some_and(|fu| !fu.is_low() && !fu.is_unknown()) {
+                continue;
+            }
+            let rob_num = get_known(&format!("{row}.rob_num"), snapshots);
+            let pc = get_known(&format!("{row}.op.PC"), snapshots);
+            if let (Some(rob_num), Some(pc)) = (rob_num, pc) {
+                state.rs.push((rob_num, pc));
+            }
+        }
+
+        let packets = |packets: &[String]| -> Vec<(usize, Option<usize>)> {
+            packets
+                .iter()
+                .filter(|packet| is_valid(packet, snapshots))
+                .filter_map(|packet| {
+                    let rob_num = get_known(&format!("{packet}.rob_num"), snapshots)?;
+                    Some((rob_num, get_known(&format!("{packet}.op.PC"), snapshots)))
+                })
+                .collect()
+        };
+        state.issue = packets(&self.issue_packets);
+        state.fu = packets(&self.fu_packets);
+
+        state.cdb = self
+            .cdb
+            .iter()
+            .filter(|row| is_valid(row, snapshots))
+            .filter_map(|row| get_known(&format!("{row}.dest_reg_idx"), snapshots))
+            .collect();
+
+        state
+    }
+
+    /// The instruction in ROB entry `rob_num` right now, if there is one.
+    pub fn rob_inst(&self, rob_num: usize, snapshots: &Snapshots) -> Option<Inst> {
+        self.sample(snapshots).rob.get(rob_num).copied().flatten()
+    }
+}
+
+impl CycleState {
+    /// Whether `inst` is in `stage` this cycle.
+    pub fn contains(&self, stage: Stage, inst: &Inst) -> bool {
+        let matches_packet = |&(rob_num, pc): &(usize, Option<usize>)| {
+            rob_num == inst.rob_num && pc.is_none_or(|pc| pc == inst.pc)
+        };
+
+        match stage {
+            Stage::Fetch => self.fetch.contains(&inst.pc),
+            Stage::Rs => self.rs.contains(&(inst.rob_num, inst.pc)),
+            Stage::Issue => self.issue.iter().any(matches_packet),
+            Stage::Fu => self.fu.iter().any(matches_packet),
+            // instructions without a destination never show up on the cdb
+            Stage::Complete => inst.dest_tag != 0 && self.cdb.contains(&inst.dest_tag),
+            Stage::Rob => self
+                .rob
+                .get(inst.rob_num)
+                .copied()
+                .flatten()
+                .is_some_and(|x| x.pc == inst.pc),
+        }
+    }
+
+    /// Whether ROB entry `rob_num` was between head and the new head `next_head`, i.e. it retired
+    /// going into the cycle with that head.
+    pub fn retired_by(&self, rob_num: usize, next_head: usize) -> bool {
+        let size = self.rob.len();
+        if size == 0 {
+            return false;
+        }
+        let offset = (rob_num + size - self.rob_head) % size;
+        let advanced = (next_head + size - self.rob_head) % size;
+        offset < advanced
+    }
+}
+
+/// First and last cycle (as positions in [`Snapshots::cycle_indices`]) an instruction spent in a
+/// stage.
+#[derive(Clone, Copy, Debug)]
+pub struct Span {
+    pub stage: Stage,
+    pub enter: usize,
+    pub leave: usize,
+    /// clock counts of `enter` and `leave`, for display
+    pub enter_clock: usize,
+    pub leave_clock: usize,
+}
+
+impl Span {
+    fn new(stage: Stage, enter: usize, leave: usize) -> Self {
+        Self {
+            stage,
+            enter,
+            leave,
+            enter_clock: enter,
+            leave_clock: leave,
+        }
+    }
+}
+
+/// Everything we could find out about where one dynamic instruction was and when.
+pub struct Lifetime {
+    pub inst: Inst,
+    pub disasm: String,
+    /// clock count of the cycle the lifetime was looked up from
+    pub cycle: usize,
+    pub spans: Vec<Span>,
+    /// clock count of the first cycle after retirement, if it retired before the end of the trace
+    pub retired: Option<usize>,
+    pub cycle_indices: Vec<usize>,
+}
+
+impl Lifetime {
+    /// Follows the instruction in ROB entry `rob_num` with PC `pc` at the current cursor back and
+    /// forward through the trace. `base` is any structure in the same core.
+    pub fn track(
+        base: &str,
+        rob_num: usize,
+        pc: usize,
+        snapshots: &mut Snapshots,
+    ) -> Result<Self, String> {
+        let pipeline = Pipeline::new(base, snapshots)
+            .ok_or_else(|| String::from("Couldn't find a ROB to follow the instruction in!"))?;
+
+        let original = snapshots.index();
+        let cycle_indices = snapshots.cycle_indices();
+        if cycle_indices.is_empty() {
+            return Err(String::from(
+                "The trace has no cycles to follow the instruction through!",
+            ));
+        }
+        let current = cycle_indices
+            .iter()
+            .position(|&i| i >= original)
+            .unwrap_or(cycle_indices.len() - 1);
+
+        snapshots.go_to(cycle_indices[current]);
+        let inst = pipeline
+            .rob_inst(rob_num, snapshots)
+            .filter(|inst| inst.pc == pc);
+        let Some(inst) = inst else {
+            snapshots.go_to(original);
+            return Err(format!("{pc:x} isn't in ROB entry {rob_num} this cycle!"));
+        };
+        let disasm = pipeline.rob_disasm(rob_num, snapshots);
+
+        // the stage checks below revisit the same cycles, so sample each one only once
+        let mut samples: HashMap<usize, CycleState> = HashMap::new();
+        let mut is_in = |stage: Stage, cycle: usize| {
+            samples
+                .entry(cycle)
+                .or_insert_with(|| {
+                    snapshots.go_to(cycle_indices[cycle]);
+                    pipeline.sample(snapshots)
+                })
+                .contains(stage, &inst)
+        };
+
+        // the rob entry bounds the whole lifetime, apart from fetch
+        let mut dispatch = current;
+        while dispatch > 0 && is_in(Stage::Rob, dispatch - 1) {
+            dispatch -= 1;
+        }
+        let mut last = current;
+        while last + 1 < cycle_indices.len() && is_in(Stage::Rob, last + 1) {
+            last += 1;
+        }
+        let retired = (last + 1 < cycle_indices.len()).then_some(last + 1);
+
+        let mut spans = Vec::new();
+
+        let fetched = (dispatch.saturating_sub(FETCH_LOOKBACK)..dispatch)
+            .rev()
+            .find(|&cycle| is_in(Stage::Fetch, cycle));
+        if let Some(leave) = fetched {
+            let mut enter = leave;
+            while enter > 0 && is_in(Stage::Fetch, enter - 1) {
+                enter -= 1;
+            }
+            spans.push(Span::new(Stage::Fetch, enter, leave));
+        }
+
+        for stage in [Stage::Rs, Stage::Issue, Stage::Fu, Stage::Complete] {
+            let mut cycles = (dispatch..=last).filter(|&cycle| is_in(stage, cycle));
+            if let Some(enter) = cycles.next() {
+                let leave = cycles.last().unwrap_or(enter);
+                spans.push(Span::new(stage, enter, leave));
+            }
+        }
+
+        spans.push(Span::new(Stage::Rob, dispatch, last));
+
+        let mut clock_of = |cycle: usize| {
+            snapshots.go_to(cycle_indices[cycle]);
+            snapshots.get_clock_count()
+        };
+        for span in spans.iter_mut() {
+            span.enter_clock = clock_of(span.enter);
+            span.leave_clock = clock_of(span.leave);
+        }
+        let cycle = clock_of(current);
+        let retired = retired.map(&mut clock_of);
+
+        snapshots.go_to(original);
+
+        Ok(Self {
+            inst,
+            disasm,
+            cycle,
+            spans,
+            retired,
+            cycle_indices,
+        })
+    }
+
+    /// Snapshot index of the cycle at `cycle` in [`Snapshots::cycle_indices`].
+    pub fn index_of(&self, cycle: usize) -> usize {
+        self.cycle_indices[cycle]
+    }
+}
+
+/// How an instruction left the ROB.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fate {
+    Retired,
+    Squashed,
+    /// still in the ROB at the end of the traced cycles
+    InFlight,
+}
+
+/// One dynamic instruction in a [`Trace`].
+#[derive(Clone, Debug)]
+pub struct TracedInst {
+    pub inst: Inst,
+    pub disasm: String,
+    /// cycle (as a position in [`Snapshots::cycle_indices`]) of the first entry in `stages`
+    pub start: usize,
+    /// the furthest stage the instruction had reached in each cycle from `start` until it left
+    /// the ROB, and whether it was actually seen in that stage's structure that cycle
+    pub stages: Vec<(Stage, bool)>,
+    pub fate: Fate,
+}
+
+impl TracedInst {
+    /// Last cycle the instruction was in the pipeline.
+    pub fn end(&self) -> usize {
+        self.start + self.stages.len() - 1
+    }
+
+    /// Stage the instruction was in at `cycle`, if it was in the pipeline then.
+    pub fn stage_at(&self, cycle: usize) -> Option<(Stage, bool)> {
+        self.stages.get(cycle.checked_sub(self.start)?).copied()
+    }
+}
+
+/// Every dynamic instruction that went through the ROB in a range of cycles, in dispatch order.
+pub struct Trace {
+    pub insts: Vec<TracedInst>,
+    /// the traced cycles, as positions in [`Snapshots::cycle_indices`]
+    pub cycles: Range<usize>,
+    /// clock count of each traced cycle
+    pub clocks: Vec<usize>,
+}
+
+impl Trace {
+    /// Reconstructs every instruction in flight during `cycles` of `cycle_indices`. Fetch is
+    /// looked for in the cycles before each dispatch, so instructions can start before `cycles`.
+    pub fn build(
+        pipeline: &Pipeline,
+        snapshots: &mut Snapshots,
+        cycle_indices: &[usize],
+        cycles: Range<usize>,
+    ) -> Self {
+        let original = snapshots.index();
+        let cycles = cycles.start..cycles.end.min(cycle_indices.len());
+        let first = cycles.start.saturating_sub(FETCH_LOOKBACK);
+
+        let mut insts: Vec<TracedInst> = Vec::new();
+        // index into insts of the instruction in each rob entry
+        let mut live: HashMap<usize, usize> = HashMap::new();
+        let mut fetch_history: Vec<Vec<usize>> = Vec::new();
+        let mut clocks = Vec::with_capacity(cycles.len());
+        let mut previous: Option<CycleState> = None;
+
+        let sampled = cycle_indices.iter().enumerate().take(cycles.end);
+        for (cycle, &index) in sampled.skip(first) {
+            snapshots.go_to(index);
+            let state = pipeline.sample(snapshots);
+            if cycle >= cycles.start {
+                clocks.push(snapshots.get_clock_count());
+            }
+
+            // everything that left the rob since last cycle either retired or got squashed
+            live.retain(|&rob_num, &mut i| {
+                let inst = &insts[i].inst;
+                if state.contains(Stage::Rob, inst)
+                    && state.rob[rob_num].is_some_and(|x| x.dest_tag == inst.dest_tag)
+                {
+                    return true;
+                }
+                let previous = previous.as_ref().unwrap();
+                insts[i].fate = if previous.retired_by(rob_num, state.rob_head) {
+                    Fate::Retired
+                } else {
+                    Fate::Squashed
+                };
+                false
+            });
+
+            // oldest first, so instructions end up in program order
+            let rob_size = state.rob.len();
+            let in_rob = (0..state.rob_count)
+                .filter_map(|offset| state.rob[(state.rob_head + offset) % rob_size].as_ref());
+            for inst in in_rob {
+                let i = match live.get(&inst.rob_num) {
+                    Some(&i) => i,
+                    None => {
+                        // it was fetched in the cycles right before it showed up in the rob
+                        let fetched = fetch_history
+                            .iter()
+                            .rev()
+                            .take_while(|pcs| pcs.contains(&inst.pc))
+                            .count();
+
+                        let mut stages = vec![(Stage::Fetch, true); fetched];
+                        stages.push((Stage::Rs, true));
+
+                        insts.push(TracedInst {
+                            inst: *inst,
+                            disasm: pipeline.rob_disasm(inst.rob_num, snapshots),
+                            start: cycle - fetched,
+                            stages,
+                            fate: Fate::InFlight,
+                        });
+                        live.insert(inst.rob_num, insts.len() - 1);
+                        continue;
+                    }
+                };
+
+                let traced = &mut insts[i];
+                let stage = [Stage::Complete, Stage::Fu, Stage::Issue, Stage::Rs]
+                    .into_iter()
+                    .find(|&stage| state.contains(stage, inst));
+                let entry = match stage {
+                    Some(stage) => (stage, true),
+                    None => (traced.stages.last().unwrap().0, false),
+                };
+                traced.stages.push(entry);
+            }
+
+            fetch_history.push(state.fetch.clone());
+            previous = Some(state);
+        }
+
+        snapshots.go_to(original);
+
+        insts.retain(|inst| inst.end() >= cycles.start);
+
+        Self {
+            insts,
+            cycles,
+            clocks,
+        }
+    }
+}
@@ -1,21 +1,126 @@
 use std::{env, path::Path, process::exit};
 
 pub use app::App;
+use perf::PerfTrace;
+use raki::Isa;
+use retire::RetireLog;
+use snapshots::Snapshots;
+use utils::IsaConfig;
 
 pub mod app;
+pub mod cache_stats;
 pub mod headers;
+pub mod invariants;
+mod kanata;
+pub mod lifetime;
 mod logging;
+pub mod memory;
+pub mod model;
+pub mod perf;
+pub mod predictor;
+pub mod program;
+pub mod retire;
 pub mod snapshots;
 pub mod structures;
+pub mod transactions;
 pub mod utils;
 pub mod var_index;
 
-fn parse_args() -> (String, usize, usize) {
-    let args: Vec<String> = env::args().collect();
+/// Things to do without opening the debugger, instead writing their output to a file.
+enum Command {
+    /// write the pipeline as a Kanata log to the given path
+    Kanata(String),
+    /// write the committed instructions in `writeback.out` format to the given path
+    Writeback(String),
+    /// write a performance counter report to the given path
+    Perf(String),
+}
+
+const FLAGS_USAGE: &str = "[--reference <log file>] [--program <.mem file>] \
+    [--isa <e.g. rv32imc>] [--kanata <output file>] [--writeback <output file>] \
+    [--perf <output file>]";
+
+/// Files to check the trace against.
+struct Inputs {
+    /// a writeback/commit log of the same program
+    reference: Option<String>,
+    /// the memory image the testbench loaded
+    program: Option<String>,
+}
+
+fn parse_args() -> (String, usize, usize, IsaConfig, Inputs, Option<Command>) {
+    let mut args: Vec<String> = env::args().collect();
+
+    let mut inputs = Inputs {
+        reference: None,
+        program: None,
+    };
+    for flag in ["--reference", "--program"] {
+        let Some(i) = args.iter().position(|arg| arg == flag) else {
+            continue;
+        };
+        let Some(path) = args.get(i + 1).cloned() else {
+            eprintln!("{flag} needs a file!");
+            eprintln!(
+                "Usage: {} <path to vcd file> [start clock cycle] [debugging length] {FLAGS_USAGE}",
+                args[0]
+            );
+            exit(1)
+        };
+        match flag {
+            "--reference" => inputs.reference = Some(path),
+            _ => inputs.program = Some(path),
+        }
+        args.drain(i..i + 2);
+    }
+
+    let mut isa = IsaConfig::default();
+    if let Some(i) = args.iter().position(|arg| arg == "--isa") {
+        let Some(parsed) = args.get(i + 1).and_then(|isa| IsaConfig::parse(isa)) else {
+            eprintln!("--isa needs an ISA string like rv32i, rv32im or rv32imc!");
+            eprintln!(
+                "Usage: {} <path to vcd file> [start clock cycle] [debugging length] {FLAGS_USAGE}",
+                args[0]
+            );
+            exit(1)
+        };
+        if inputs.program.is_some() && (parsed.a || parsed.c || matches!(parsed.xlen, Isa::Rv64)) {
+            eprintln!("--program only works with rv32i and rv32im, the model doesn't run atomic, compressed or 64-bit code!");
+            exit(1)
+        }
+        isa = parsed;
+        args.drain(i..i + 2);
+    }
+
+    let mut command = None;
+    for flag in ["--kanata", "--writeback", "--perf"] {
+        let Some(i) = args.iter().position(|arg| arg == flag) else {
+            continue;
+        };
+        let Some(path) = args.get(i + 1).cloned() else {
+            eprintln!("{flag} needs an output file!");
+            eprintln!(
+                "Usage: {} <path to vcd file> [start clock cycle] [debugging length] {FLAGS_USAGE}",
+                args[0]
+            );
+            exit(1)
+        };
+        if command.is_some() {
+            eprintln!("Only one of --kanata, --writeback and --perf can be given at a time!");
+            exit(1)
+        }
+        command = Some(match flag {
+            "--kanata" => Command::Kanata(path),
+            "--writeback" => Command::Writeback(path),
+            _ => Command::Perf(path),
+        });
+        args.drain(i..i + 2);
+    }
+
     if args.len() < 2 {
         eprintln!("Missing vcd file argument!");
         eprintln!(
-            "Usage: {} <path to vcd file> [start clock cycle] [debugging length]",
+            "Usage: {} <path to vcd file> [start clock cycle] [debugging length] {FLAGS_USAGE}",
             args[0]
         );
         exit(1)
@@ -65,7 +170,14 @@ fn parse_args() -> (String, usize, usize) {
         build_name
     };
 
-    (correct_name, start_clock, debugging_length)
+    (
+        correct_name,
+        start_clock,
+        debugging_length,
+        isa,
+        inputs,
+        command,
+    )
 }
 
 // fn main() {
@@ -76,12 +188,51 @@ fn parse_args() -> (String, usize, usize) {
 // }
 
 fn main() -> color_eyre::Result<()> {
-    let (filename, start_clock, debugging_length) = parse_args();
+    let (filename, start_clock, debugging_length, isa, inputs, command) = parse_args();
+
+    if let Some(command) = command {
+        let mut snapshots = Snapshots::new(&filename, start_clock, debugging_length)?;
+        snapshots.context.isa = isa;
+        match command {
+            Command::Kanata(path) => {
+                let num_insts = kanata::export(&mut snapshots, &path)?;
+                println!("Wrote {num_insts} instructions to {path}");
+            }
+            Command::Writeback(path) => {
+                let Some(log) = RetireLog::extract(&mut snapshots) else {
+                    eprintln!(
+                        "Couldn't find a ROB or committed_insts to extract retirements from!"
+                    );
+                    exit(1)
+                };
+                log.write_writeback(&path)?;
+                println!("Wrote {} instructions to {path}", log.entries.len());
+            }
+            Command::Perf(path) => {
+                let base = snapshots.get_base();
+                let log = RetireLog::extract(&mut snapshots);
+                let trace = PerfTrace::collect(&base, log.as_ref(), &mut snapshots);
+                trace.write_report(&path)?;
+                println!(
+                    "Wrote counters for {} cycles to {path}",
+                    trace.samples.len()
+                );
+            }
+        }
+        return Ok(());
+    }
 
     logging::initialize_logging()?;
     color_eyre::install()?;
     let terminal = ratatui::init();
-    let app = App::new(&filename, start_clock, debugging_length);
+    let app = App::new(
+        &filename,
+        start_clock,
+        debugging_length,
+        isa,
+        inputs.reference,
+        inputs.program,
+    );
     let result = app.run(terminal);
     ratatui::restore();
     result
@@ -0,0 +1,152 @@
+//! Contents of main memory over the course of the trace.
+use std::collections::HashMap;
+
+use crate::{
+    program::MemoryImage,
+    retire::{RetireLog, Store},
+    snapshots::Snapshots,
+    utils::get_known,
+};
+
+/// `proc2mem_command` value for a write, see `parse_mem_command`.
+const MEM_STORE: usize = 0b10;
+
+/// Where a write to memory was seen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteSource {
+    /// on the processor's memory bus, e.g. a dcache writeback
+    Bus,
+    /// a store retiring from the store queue, which may still be sitting in the dcache
+    Commit,
+}
+
+/// A write to memory and when it happened.
+#[derive(Clone, Copy, Debug)]
+pub struct MemWrite {
+    /// as a position in [`Snapshots::cycle_indices`]
+    pub cycle: usize,
+    /// clock count of `cycle`
+    pub clock: usize,
+    pub store: Store,
+    pub source: WriteSource,
+}
+
+/// The initial memory image and every write made to it, to rebuild memory at any cycle.
+pub struct MemoryHistory {
+    initial: MemoryImage,
+    /// in the order they happened
+    pub writes: Vec<MemWrite>,
+    /// indices into `writes` of the ones to each byte, so reading memory at a cycle doesn't have to
+    /// replay everything before it
+    by_byte: HashMap<usize, Vec<usize>>,
+}
+
+/// The write on the memory bus under `base` this cycle, if there is one. Memory is 8 bytes wide
+/// and takes smaller writes from the byte lanes they're addressed to.
+fn bus_write(base: &str, snapshots: &Snapshots) -> Option<Store> {
+    if get_known(&format!("{base}.proc2mem_command"), snapshots)? != MEM_STORE {
+        return None;
+    }
+    let size = 1 << get_known(&format!("{base}.proc2mem_size"), snapshots)?;
+    let addr = get_known(&format!("{base}.proc2mem_addr"), snapshots)? & !(size - 1);
+    let data = get_known(&format!("{base}.proc2mem_data.dbbl_level"), snapshots)?;
+    Some(Store::new(addr, size, data >> (8 * (addr & 0b111))))
+}
+
+impl MemoryHistory {
+    /// Collects the writes on the memory bus of the testbench at `base` and the stores in `log`,
+    /// what retired in the first core, on top of `initial`.
+    pub fn extract(
+        base: &str,
+        initial: MemoryImage,
+        log: Option<&RetireLog>,
+        snapshots: &mut Snapshots,
+    ) -> Self {
+        let mut writes = Vec::new();
+
+        if snapshots
+            .get_var(&format!("{base}.proc2mem_command"))
+            .is_some()
+        {
+            let original = snapshots.index();
+            for (cycle, index) in snapshots.cycle_indices().into_iter().enumerate() {
+                snapshots.go_to(index);
+                if let Some(store) = bus_write(base, snapshots) {
+                    writes.push(MemWrite {
+                        cycle,
+                        clock: snapshots.get_clock_count(),
+                        store,
+                        source: WriteSource::Bus,
+                    });
+                }
+            }
+            snapshots.go_to(original);
+        }
+
+        if let Some(log) = log {
+            writes.extend(log.entries.iter().filter_map(|entry| {
+                Some(MemWrite {
+                    cycle: entry.cycle,
+                    clock: entry.clock,
+                    store: entry.store?,
+                    source: WriteSource::Commit,
+                })
+            }));
+        }
+        // a store commits before the dcache can write it back, so commits go first
+        writes.sort_by_key(|write| (write.cycle, write.source == WriteSource::Bus));
+
+        let mut by_byte: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, write) in writes.iter().enumerate() {
+            for addr in write.store.addr..write.store.addr + write.store.size {
+                by_byte.entry(addr).or_default().push(i);
+            }
+        }
+
+        Self {
+            initial,
+            writes,
+            by_byte,
+        }
+    }
+
+    /// Number of writes made up to and including `cycle`.
+    pub fn num_writes(&self, cycle: usize) -> usize {
+        self.writes.partition_point(|write| write.cycle <= cycle)
+    }
+
+    /// The last write to the byte at `addr` as of the end of `cycle`.
+    fn last_write_to(&self, addr: usize, cycle: usize) -> Option<&MemWrite> {
+        let writes = self.by_byte.get(&addr)?;
+        let num_writes = writes.partition_point(|&i| self.writes[i].cycle <= cycle);
+        writes[..num_writes].last().map(|&i| &self.writes[i])
+    }
+
+    /// Reads `size` bytes little-endian starting at `addr`, as of the end of `cycle`.
+    pub fn read(&self, addr: usize, size: usize, cycle: usize) -> usize {
+        (addr..addr + size).rev().fold(0, |value, addr| {
+            let byte = match self.last_write_to(addr, cycle) {
+                Some(write) => (write.store.value >> (8 * (addr - write.store.addr))) & 0xff,
+                None => self.initial.read(addr, 1),
+            };
+            (value << 8) | byte
+        })
+    }
+
+    /// Cycle the 4-byte word at `word` was last written in, as of the end of `cycle`.
+    pub fn last_written(&self, word: usize, cycle: usize) -> Option<usize> {
+        (word..word + 4)
+            .filter_map(|addr| self.last_write_to(addr, cycle))
+            .map(|write| write.cycle)
+            .max()
+    }
+
+    /// Every address that's loaded or ever written.
+    pub fn addrs(&self) -> impl Iterator<Item = usize> + '_ {
+        let written = self.writes.iter().flat_map(|write| {
+            let Store { addr, size, .. } = write.store;
+            addr..addr + size
+        });
+        self.initial.addrs().chain(written)
+    }
+}
@@ -0,0 +1,310 @@
+//! A small RV32IM interpreter, run over the program image to get what should have retired.
+use raki::{BaseIOpcode, MOpcode, OpcodeKind, PrivOpcode};
+
+use crate::{
+    program::MemoryImage,
+    retire::{Commit, Store},
+    utils::IsaConfig,
+};
+
+/// Major opcode of `fence` and `fence.i`.
+const MISC_MEM: u32 = 0b000_1111;
+
+/// Architectural state of a hart, starting from reset.
+pub struct Model {
+    pub pc: u32,
+    pub regs: [u32; 32],
+    pub memory: MemoryImage,
+    /// what instructions are decoded as, never with the A extension
+    pub isa: IsaConfig,
+    /// why it stopped, once it executes `wfi`/`ecall`/`ebreak` or something it can't decode
+    pub halt: Option<String>,
+}
+
+impl Model {
+    /// Atomics aren't run, so even with the default ISA, which has them, they don't decode and
+    /// halt the model like anything else it can't execute.
+    pub fn new(memory: MemoryImage, isa: IsaConfig) -> Self {
+        Self {
+            pc: 0,
+            regs: [0; 32],
+            memory,
+            isa: IsaConfig { a: false, ..isa },
+            halt: None,
+        }
+    }
+
+    /// The first `count` instructions the program commits, fewer if it halts before then, and why
+    /// it halted if it did.
+    pub fn run(memory: MemoryImage, isa: IsaConfig, count: usize) -> (Vec<Commit>, Option<String>) {
+        let mut model = Self::new(memory, isa);
+        let commits = (0..count).map_while(|_| model.step()).collect();
+        (commits, model.halt)
+    }
+
+    /// Halts on an instruction the model can't execute, which doesn't commit.
+    fn unsupported(&mut self, bits: u32) -> Option<Commit> {
+        self.halt = Some(format!("couldn't execute {bits:#010x} at {:#x}", self.pc));
+        None
+    }
+
+    fn reg(&self, index: Option<usize>) -> u32 {
+        index.map_or(0, |i| self.regs[i])
+    }
+
+    fn load(&self, addr: u32, size: usize) -> u32 {
+        self.memory.read(addr as usize, size) as u32
+    }
+
+    /// Executes one instruction, returning what it committed. The halting instruction itself
+    /// still commits; after that this returns `None`.
+    pub fn step(&mut self) -> Option<Commit> {
+        if self.halt.is_some() {
+            return None;
+        }
+
+        let pc = self.pc;
+        let bits = self.load(pc, 4);
+        // raki decodes `fence` as Zifencei and doesn't know `fence.i`; neither does anything with
+        // one hart and no caches
+        if bits & 0x7f == MISC_MEM && (bits >> 12) & 0b111 <= 1 {
+            self.pc = pc.wrapping_add(4);
+            return Some(Commit {
+                pc: pc as usize,
+                write: None,
+                store: None,
+            });
+        }
+        let Some(inst) = self.isa.decode(bits as usize) else {
+            return self.unsupported(bits);
+        };
+
+        let rs1 = self.reg(inst.rs1);
+        let rs2 = self.reg(inst.rs2);
+        let imm = inst.imm.unwrap_or(0) as u32;
+        let mut next_pc = pc.wrapping_add(self.isa.inst_len(bits as usize) as u32);
+        let mut result = None;
+        let mut store = None;
+
+        match &inst.opc {
+            OpcodeKind::BaseI(op) => match op {
+                BaseIOpcode::LUI => result = Some(imm),
+                BaseIOpcode::AUIPC => result = Some(pc.wrapping_add(imm)),
+                BaseIOpcode::JAL => {
+                    result = Some(next_pc);
+                    next_pc = pc.wrapping_add(imm);
+                }
+                BaseIOpcode::JALR => {
+                    result = Some(next_pc);
+                    next_pc = rs1.wrapping_add(imm) & !1;
+                }
+                BaseIOpcode::BEQ
+                | BaseIOpcode::BNE
+                | BaseIOpcode::BLT
+                | BaseIOpcode::BGE
+                | BaseIOpcode::BLTU
+                | BaseIOpcode::BGEU => {
+                    let taken = match op {
+                        BaseIOpcode::BEQ => rs1 == rs2,
+                        BaseIOpcode::BNE => rs1 != rs2,
+                        BaseIOpcode::BLT => (rs1 as i32) < (rs2 as i32),
+                        BaseIOpcode::BGE => (rs1 as i32) >= (rs2 as i32),
+                        BaseIOpcode::BLTU => rs1 < rs2,
+                        _ => rs1 >= rs2,
+                    };
+                    if taken {
+                        next_pc = pc.wrapping_add(imm);
+                    }
+                }
+                BaseIOpcode::LB => result = Some(self.load(rs1.wrapping_add(imm), 1) as i8 as u32),
+                BaseIOpcode::LH => result = Some(self.load(rs1.wrapping_add(imm), 2) as i16 as u32),
+                BaseIOpcode::LW => result = Some(self.load(rs1.wrapping_add(imm), 4)),
+                BaseIOpcode::LBU => result = Some(self.load(rs1.wrapping_add(imm), 1)),
+                BaseIOpcode::LHU => result = Some(self.load(rs1.wrapping_add(imm), 2)),
+                BaseIOpcode::SB | BaseIOpcode::SH | BaseIOpcode::SW => {
+                    let size = match op {
+                        BaseIOpcode::SB => 1,
+                        BaseIOpcode::SH => 2,
+                        _ => 4,
+                    };
+                    let addr = rs1.wrapping_add(imm) as usize;
+                    self.memory.write(addr, size, rs2 as usize);
+                    store = Some(Store::new(addr, size, rs2 as usize));
+                }
+                BaseIOpcode::ADDI => result = Some(rs1.wrapping_add(imm)),
+                BaseIOpcode::SLTI => result = Some(((rs1 as i32) < (imm as i32)) as u32),
+                BaseIOpcode::SLTIU => result = Some((rs1 < imm) as u32),
+                BaseIOpcode::XORI => result = Some(rs1 ^ imm),
+                BaseIOpcode::ORI => result = Some(rs1 | imm),
+                BaseIOpcode::ANDI => result = Some(rs1 & imm),
+                BaseIOpcode::SLLI => result = Some(rs1 << (imm & 0x1f)),
+                BaseIOpcode::SRLI => result = Some(rs1 >> (imm & 0x1f)),
+                BaseIOpcode::SRAI => result = Some(((rs1 as i32) >> (imm & 0x1f)) as u32),
+                BaseIOpcode::ADD => result = Some(rs1.wrapping_add(rs2)),
+                BaseIOpcode::SUB => result = Some(rs1.wrapping_sub(rs2)),
+                BaseIOpcode::SLL => result = Some(rs1 << (rs2 & 0x1f)),
+                BaseIOpcode::SLT => result = Some(((rs1 as i32) < (rs2 as i32)) as u32),
+                BaseIOpcode::SLTU => result = Some((rs1 < rs2) as u32),
+                BaseIOpcode::XOR => result = Some(rs1 ^ rs2),
+                BaseIOpcode::SRL => result = Some(rs1 >> (rs2 & 0x1f)),
+                BaseIOpcode::SRA => result = Some(((rs1 as i32) >> (rs2 & 0x1f)) as u32),
+                BaseIOpcode::OR => result = Some(rs1 | rs2),
+                BaseIOpcode::AND => result = Some(rs1 & rs2),
+                BaseIOpcode::ECALL | BaseIOpcode::EBREAK => {
+                    self.halt = Some(format!("hit {inst} at {pc:#x}"))
+                }
+                // rv64 only, which decoding as rv32 never gives
+                _ => return self.unsupported(bits),
+            },
+            OpcodeKind::M(op) => {
+                let (signed1, signed2) = (rs1 as i32, rs2 as i32);
+                result = Some(match op {
+                    MOpcode::MUL => rs1.wrapping_mul(rs2),
+                    MOpcode::MULH => ((signed1 as i64 * signed2 as i64) >> 32) as u32,
+                    MOpcode::MULHSU => ((signed1 as i64 * rs2 as i64) >> 32) as u32,
+                    MOpcode::MULHU => ((rs1 as u64 * rs2 as u64) >> 32) as u32,
+                    // division by zero and overflow don't trap, see the spec's table 7.1
+                    MOpcode::DIV => match signed2 {
+                        0 => u32::MAX,
+                        _ => signed1.wrapping_div(signed2) as u32,
+                    },
+                    MOpcode::DIVU => rs1.checked_div(rs2).unwrap_or(u32::MAX),
+                    MOpcode::REM => match signed2 {
+                        0 => rs1,
+                        _ => signed1.wrapping_rem(signed2) as u32,
+                    },
+                    MOpcode::REMU => rs1.checked_rem(rs2).unwrap_or(rs1),
+                    _ => return self.unsupported(bits),
+                });
+            }
+            // wfi is how our programs halt
+            OpcodeKind::Priv(PrivOpcode::WFI) => self.halt = Some(format!("hit {inst} at {pc:#x}")),
+            _ => return self.unsupported(bits),
+        }
+
+        let write = inst.rd.filter(|&rd| rd != 0).zip(result);
+        if let Some((rd, value)) = write {
+            self.regs[rd] = value;
+        }
+        self.pc = next_pc;
+
+        Some(Commit {
+            pc: pc as usize,
+            write: write.map(|(rd, value)| (rd, value as usize)),
+            store,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OP: u32 = 0b011_0011;
+    const OP_IMM: u32 = 0b001_0011;
+    const JALR: u32 = 0b110_0111;
+    const MULDIV: u32 = 0b000_0001;
+
+    fn r_type(funct7: u32, rs2: u32, rs1: u32, funct3: u32, rd: u32) -> u32 {
+        (funct7 << 25) | (rs2 << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | OP
+    }
+
+    fn i_type(imm: u32, rs1: u32, funct3: u32, rd: u32, opcode: u32) -> u32 {
+        (imm << 20) | (rs1 << 15) | (funct3 << 12) | (rd << 7) | opcode
+    }
+
+    /// A model about to run `program` from 0, with x1 and x2 set.
+    fn model(program: &[u32], x1: u32, x2: u32) -> Model {
+        let mut memory = MemoryImage::default();
+        for (i, &inst) in program.iter().enumerate() {
+            memory.write(4 * i, 4, inst as usize);
+        }
+        let mut model = Model::new(memory, IsaConfig::default());
+        model.regs[1] = x1;
+        model.regs[2] = x2;
+        model
+    }
+
+    /// What x3 gets from `rd = x1 <op> x2` for the M extension op with `funct3`.
+    fn muldiv(funct3: u32, x1: u32, x2: u32) -> usize {
+        let commit = model(&[r_type(MULDIV, 2, 1, funct3, 3)], x1, x2)
+            .step()
+            .unwrap();
+        commit.write.unwrap().1
+    }
+
+    #[test]
+    fn high_multiplies() {
+        // -2^31 and -1 signed, 2^31 and 2^32 - 1 unsigned
+        let (x1, x2) = (0x8000_0000, 0xffff_ffff);
+        assert_eq!(muldiv(0b001, x1, x2), 0, "mulh");
+        assert_eq!(muldiv(0b010, x1, x2), 0x8000_0000, "mulhsu");
+        assert_eq!(muldiv(0b011, x1, x2), 0x7fff_ffff, "mulhu");
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(muldiv(0b100, 7, 0), 0xffff_ffff, "div");
+        assert_eq!(muldiv(0b101, 7, 0), 0xffff_ffff, "divu");
+        assert_eq!(muldiv(0b110, 7, 0), 7, "rem");
+        assert_eq!(muldiv(0b111, 7, 0), 7, "remu");
+    }
+
+    #[test]
+    fn division_overflow() {
+        assert_eq!(muldiv(0b100, 0x8000_0000, 0xffff_ffff), 0x8000_0000, "div");
+        assert_eq!(muldiv(0b110, 0x8000_0000, 0xffff_ffff), 0, "rem");
+    }
+
+    #[test]
+    fn arithmetic_shifts_fill_with_the_sign() {
+        let sra = r_type(0b010_0000, 2, 1, 0b101, 3);
+        let srai = i_type(0b0100_0000_0100, 1, 0b101, 4, OP_IMM);
+        let mut model = model(&[sra, srai], 0x8000_0000, 4);
+        assert_eq!(model.step().unwrap().write, Some((3, 0xf800_0000)));
+        assert_eq!(model.step().unwrap().write, Some((4, 0xf800_0000)));
+    }
+
+    #[test]
+    fn jalr_clears_the_low_bit() {
+        let mut model = model(&[i_type(0, 1, 0, 5, JALR)], 0x101, 0);
+        assert_eq!(model.step().unwrap().write, Some((5, 4)));
+        assert_eq!(model.pc, 0x100);
+    }
+
+    #[test]
+    fn fences_do_nothing() {
+        let fence = 0x0ff0_000f;
+        let fence_i = 0x0000_100f;
+        let mut model = model(&[fence, fence_i], 0, 0);
+        for pc in [0, 4] {
+            let commit = model.step().unwrap();
+            assert_eq!((commit.pc, commit.write, commit.store), (pc, None, None));
+        }
+        assert_eq!(model.pc, 8);
+        assert!(model.halt.is_none());
+    }
+
+    #[test]
+    fn halts_on_what_it_cant_execute() {
+        let mut model = model(&[0xffff_ffff], 0, 0);
+        assert!(model.step().is_none());
+        assert_eq!(
+            model.halt.as_deref(),
+            Some("couldn't execute 0xffffffff at 0x0")
+        );
+        assert_eq!(model.pc, 0);
+    }
+
+    #[test]
+    fn atomics_halt_even_though_the_default_isa_decodes_them() {
+        let amoadd_w = (2 << 20) | (1 << 15) | (0b010 << 12) | (3 << 7) | 0b010_1111;
+        assert!(IsaConfig::default().decode(amoadd_w as usize).is_some());
+        let mut model = model(&[amoadd_w], 0, 0);
+        assert!(model.step().is_none());
+        assert_eq!(
+            model.halt.as_deref(),
+            Some("couldn't execute 0x0020a1af at 0x0")
+        );
+    }
+}
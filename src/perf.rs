@@ -0,0 +1,504 @@
+//! Performance counters, sampled every cycle of the trace so they can be summed over any window.
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    ops::Range,
+};
+
+use raki::{AOpcode, BaseIOpcode, COpcode, OpcodeKind};
+
+use crate::{
+    retire::RetireLog,
+    snapshots::{Snapshots, VerilogValue},
+    structures::nearest_marker,
+    utils::{get_known, indexed},
+};
+
+/// Cycles per row of the windowed table in the report.
+pub const REPORT_WINDOW: usize = 1000;
+
+/// What a cache did in one cycle.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CacheSample {
+    pub hits: usize,
+    pub misses: usize,
+    /// valid MSHRs
+    pub mshrs: usize,
+}
+
+/// Why nothing retired in a cycle, by what the ROB was doing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stall {
+    /// a branch mispredicted and the ROB hasn't retired anything since
+    Recovery,
+    /// the ROB is empty while the icache is missing
+    ICacheMiss,
+    /// the ROB is empty for any other reason
+    Fetch,
+    /// the head is a load that hasn't completed, usually a dcache miss
+    Load,
+    /// the head is a multiply or divide that hasn't completed
+    Mult,
+    /// the head isn't ready and the store queue is full
+    SqFull,
+    /// the head isn't ready and the RS is full
+    RsFull,
+    /// the head isn't ready for some other reason
+    Other,
+}
+
+impl Stall {
+    pub const ALL: [Stall; 8] = [
+        Stall::Recovery,
+        Stall::ICacheMiss,
+        Stall::Fetch,
+        Stall::Load,
+        Stall::Mult,
+        Stall::SqFull,
+        Stall::RsFull,
+        Stall::Other,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Stall::Recovery => "branch recovery",
+            Stall::ICacheMiss => "icache miss",
+            Stall::Fetch => "fetch",
+            Stall::Load => "load/dcache miss",
+            Stall::Mult => "mult latency",
+            Stall::SqFull => "SQ full",
+            Stall::RsFull => "RS full",
+            Stall::Other => "other",
+        }
+    }
+}
+
+/// Counters for one cycle.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Sample {
+    pub retired: usize,
+    /// why nothing retired, if nothing did and there's a ROB to tell from
+    pub stall: Option<Stall>,
+    /// branches resolved by the branch FUs
+    pub branches: usize,
+    pub mispredicts: usize,
+    pub dcache: CacheSample,
+    pub icache: CacheSample,
+    /// valid entries in each structure
+    pub rob: usize,
+    pub rs: usize,
+    pub sq: usize,
+}
+
+/// Entries in each structure the counters were read from, `None` for structures the design
+/// doesn't have.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Capacity {
+    pub rob: Option<usize>,
+    pub rs: Option<usize>,
+    pub sq: Option<usize>,
+    pub dcache_mshrs: Option<usize>,
+    pub icache_mshrs: Option<usize>,
+    /// whether there are caches with query signals to count hits from
+    pub dcache: bool,
+    pub icache: bool,
+    /// whether there are branch FUs and a branch stack to count mispredicts from
+    pub branches: bool,
+}
+
+fn is_load(opc: &OpcodeKind) -> bool {
+    use BaseIOpcode::{LB, LBU, LD, LH, LHU, LW, LWU};
+    matches!(
+        opc,
+        OpcodeKind::BaseI(LB | LH | LW | LBU | LHU | LWU | LD)
+            | OpcodeKind::C(COpcode::LW | COpcode::LWSP | COpcode::LD | COpcode::LDSP)
+            | OpcodeKind::A(AOpcode::LR_W | AOpcode::LR_D)
+    )
+}
+
+fn is_valid(base: &str, snapshots: &Snapshots) -> bool {
+    snapshots
+        .get_var(&format!("{base}.valid"))
+        .is_some_and(VerilogValue::is_high)
+}
+
+/// Hit/miss tracking for a cache. A query hits if the cache answers it in the cycle it's
+/// presented; a query that doesn't is a miss, and isn't counted again while it's held waiting.
+struct CacheProbe {
+    base: String,
+    mshrs: Vec<String>,
+    /// (command, addr) of the query that missed and hasn't been answered yet
+    pending: Option<(usize, usize)>,
+}
+
+impl CacheProbe {
+    fn new(marker: &str, near: &str, snapshots: &Snapshots) -> Option<Self> {
+        let base = nearest_marker(&snapshots.header, marker, near)?;
+        snapshots.get_var(&format!("{base}.query_command"))?;
+        Some(Self {
+            mshrs: indexed(&format!("{base}.waiting_commands"), snapshots),
+            base,
+            pending: None,
+        })
+    }
+
+    fn sample(&mut self, snapshots: &Snapshots) -> CacheSample {
+        let mut sample = CacheSample {
+            mshrs: self
+                .mshrs
+                .iter()
+                .filter(|mshr| is_valid(mshr, snapshots))
+                .count(),
+            ..Default::default()
+        };
+
+        let command = get_known(&format!("{}.query_command", self.base), snapshots);
+        let addr = get_known(&format!("{}.query_addr", self.base), snapshots);
+        let Some(query) = command.filter(|&command| command != 0).zip(addr) else {
+            self.pending = None;
+            return sample;
+        };
+        let answered = is_valid(&self.base, snapshots);
+
+        if self.pending != Some(query) {
+            if answered {
+                sample.hits += 1;
+            } else {
+                sample.misses += 1;
+            }
+        }
+        self.pending = (!answered).then_some(query);
+        sample
+    }
+}
+
+/// Every cycle's counters.
+pub struct PerfTrace {
+    /// one per cycle, in order
+    pub samples: Vec<Sample>,
+    /// clock count of each cycle
+    pub clocks: Vec<usize>,
+    pub capacity: Capacity,
+}
+
+impl PerfTrace {
+    /// Samples the core the structure at `base` is in (or the testbench, for designs without a
+    /// ROB) every cycle. `log` is what it retired, see [`RetireLog::extract_at`].
+    pub fn collect(base: &str, log: Option<&RetireLog>, snapshots: &mut Snapshots) -> Self {
+        let header = &snapshots.header;
+        let rob = nearest_marker(header, "rob", base);
+        let near = rob.clone().unwrap_or_else(|| base.to_owned());
+        let rs = nearest_marker(header, "rs", &near)
+            .map(|rs| indexed(&format!("{rs}.entries"), snapshots));
+        let sq = nearest_marker(header, "store_queue", &near);
+        let bstack = nearest_marker(header, "bstack", &near);
+        let branch_outputs = nearest_marker(header, "fu", &near)
+            .map(|fu| indexed(&format!("{fu}.branch_output_packets"), snapshots))
+            .unwrap_or_default();
+        let mut dcache = CacheProbe::new("dcache", &near, snapshots);
+        let mut icache = CacheProbe::new("icache", &near, snapshots);
+
+        let capacity = Capacity {
+            rob: rob
+                .as_ref()
+                .map(|rob| indexed(&format!("{rob}.entries"), snapshots).len()),
+            rs: rs.as_ref().map(Vec::len),
+            sq: sq
+                .as_ref()
+                .map(|sq| indexed(&format!("{sq}.entries"), snapshots).len()),
+            dcache_mshrs: dcache
+                .as_ref()
+                .map(|cache| cache.mshrs.len())
+                .filter(|&mshrs| mshrs > 0),
+            icache_mshrs: icache
+                .as_ref()
+                .map(|cache| cache.mshrs.len())
+                .filter(|&mshrs| mshrs > 0),
+            dcache: dcache.is_some(),
+            icache: icache.is_some(),
+            branches: bstack.is_some() && !branch_outputs.is_empty(),
+        };
+
+        let cycle_indices = snapshots.cycle_indices();
+        let mut samples = vec![Sample::default(); cycle_indices.len()];
+        let mut clocks = Vec::with_capacity(cycle_indices.len());
+
+        for entry in log.iter().flat_map(|log| log.entries.iter()) {
+            samples[entry.cycle].retired += 1;
+        }
+
+        // whether a branch mispredicted and nothing has retired since
+        let mut recovering = false;
+        let original = snapshots.index();
+        for (sample, &index) in samples.iter_mut().zip(cycle_indices.iter()) {
+            snapshots.go_to(index);
+            clocks.push(snapshots.get_clock_count());
+
+            sample.branches = branch_outputs
+                .iter()
+                .filter(|packet| is_valid(packet, snapshots))
+                .count();
+            // a mispredicted branch's bit is raised for the cycle it squashes everything after it
+            sample.mispredicts = bstack
+                .as_ref()
+                .and_then(|bstack| get_known(&format!("{bstack}.bmask_squash"), snapshots))
+                .map_or(0, |mask| mask.count_ones() as usize);
+            if let Some(dcache) = dcache.as_mut() {
+                sample.dcache = dcache.sample(snapshots);
+            }
+            if let Some(icache) = icache.as_mut() {
+                sample.icache = icache.sample(snapshots);
+            }
+
+            if let Some(rob) = &rob {
+                sample.rob = get_known(&format!("{rob}.size"), snapshots).unwrap_or(0);
+            }
+            if let Some(rs) = &rs {
+                sample.rs = rs
+                    .iter()
+                    .filter(|row| {
+                        snapshots
+                            .get_var(&format!("{row}.fu"))
+                            .is_some_and(|fu| !fu.is_low() && !fu.is_unknown())
+                    })
+                    .count();
+            }
+            if let Some(sq) = &sq {
+                sample.sq = get_known(&format!("{sq}.size"), snapshots).unwrap_or(0);
+            }
+
+            recovering = (recovering || sample.mispredicts > 0) && sample.retired == 0;
+            if let Some(rob) = rob.as_ref().filter(|_| sample.retired == 0) {
+                let icache_missing = icache
+                    .as_ref()
+                    .is_some_and(|icache| icache.pending.is_some());
+                sample.stall = Some(if recovering {
+                    Stall::Recovery
+                } else if sample.rob == 0 && icache_missing {
+                    Stall::ICacheMiss
+                } else if sample.rob == 0 {
+                    Stall::Fetch
+                } else {
+                    let head = get_known(&format!("{rob}.head"), snapshots).unwrap_or(0);
+                    let opc =
+                        get_known(&format!("{rob}.entries[{head}].info.inst.inst"), snapshots)
+                            .and_then(|bits| snapshots.context.isa.decode(bits))
+                            .map(|inst| inst.opc);
+                    match opc {
+                        Some(opc) if is_load(&opc) => Stall::Load,
+                        Some(OpcodeKind::M(_)) => Stall::Mult,
+                        _ if capacity.sq.is_some_and(|sq| sample.sq >= sq) => Stall::SqFull,
+                        _ if capacity.rs.is_some_and(|rs| sample.rs >= rs) => Stall::RsFull,
+                        _ => Stall::Other,
+                    }
+                });
+            }
+        }
+        snapshots.go_to(original);
+
+        Self {
+            samples,
+            clocks,
+            capacity,
+        }
+    }
+
+    /// Cycles nothing retired in for each reason, in the order of [`Stall::ALL`].
+    pub fn stalls(&self) -> [usize; Stall::ALL.len()] {
+        Stall::ALL.map(|stall| {
+            self.samples
+                .iter()
+                .filter(|sample| sample.stall == Some(stall))
+                .count()
+        })
+    }
+
+    /// The longest run of consecutive cycles that stalled for `stall`, the first one if there's a
+    /// tie.
+    pub fn longest_stall(&self, stall: Stall) -> Option<Range<usize>> {
+        let mut longest: Option<Range<usize>> = None;
+        let mut start = 0;
+        for run in self.samples.chunk_by(|a, b| a.stall == b.stall) {
+            let is_longer = longest.as_ref().is_none_or(|range| run.len() > range.len());
+            if run[0].stall == Some(stall) && is_longer {
+                longest = Some(start..start + run.len());
+            }
+            start += run.len();
+        }
+        longest
+    }
+
+    /// Totals over the cycles in `range`.
+    pub fn summarize(&self, range: Range<usize>) -> Summary {
+        let range = range.start.min(self.samples.len())..range.end.min(self.samples.len());
+        let samples = &self.samples[range.clone()];
+        let sum = |f: fn(&Sample) -> usize| samples.iter().map(f).sum::<usize>();
+        let max = |f: fn(&Sample) -> usize| samples.iter().map(f).max().unwrap_or(0);
+
+        let occupancy = |f: fn(&Sample) -> usize, capacity: Option<usize>| {
+            capacity.map(|capacity| Occupancy {
+                average: sum(f) as f64 / samples.len().max(1) as f64,
+                max: max(f),
+                capacity,
+            })
+        };
+
+        Summary {
+            cycles: samples.len(),
+            clocks: self.clocks.get(range.start).copied().unwrap_or(0)
+                ..self
+                    .clocks
+                    .get(range.end.wrapping_sub(1))
+                    .map_or(0, |c| c + 1),
+            retired: sum(|s| s.retired),
+            branches: self
+                .capacity
+                .branches
+                .then(|| (sum(|s| s.branches), sum(|s| s.mispredicts))),
+            dcache: self.capacity.dcache.then(|| {
+                (
+                    sum(|s| s.dcache.hits),
+                    sum(|s| s.dcache.hits + s.dcache.misses),
+                )
+            }),
+            icache: self.capacity.icache.then(|| {
+                (
+                    sum(|s| s.icache.hits),
+                    sum(|s| s.icache.hits + s.icache.misses),
+                )
+            }),
+            dcache_mshrs: occupancy(|s| s.dcache.mshrs, self.capacity.dcache_mshrs),
+            icache_mshrs: occupancy(|s| s.icache.mshrs, self.capacity.icache_mshrs),
+            rob: occupancy(|s| s.rob, self.capacity.rob),
+            rs: occupancy(|s| s.rs, self.capacity.rs),
+            sq: occupancy(|s| s.sq, self.capacity.sq),
+        }
+    }
+
+    /// Writes the whole-trace summary, followed by a table of windows of [`REPORT_WINDOW`]
+    /// cycles.
+    pub fn write_report(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        let whole = self.summarize(0..self.samples.len());
+        writeln!(file, "Whole trace (clocks {:?}):", whole.clocks)?;
+        for (name, value) in whole.rows() {
+            writeln!(file, "  {name:<16}{value}")?;
+        }
+
+        writeln!(file)?;
+        writeln!(file, "Every {REPORT_WINDOW} cycles:")?;
+        writeln!(
+            file,
+            "  {:>10} {:>6} {:>11} {:>11} {:>11} {:>6} {:>6} {:>6}",
+            "clock", "IPC", "mispredict", "dcache hit", "icache hit", "ROB", "RS", "SQ"
+        )?;
+        for start in (0..self.samples.len()).step_by(REPORT_WINDOW) {
+            let window = self.summarize(start..start + REPORT_WINDOW);
+            let avg = |occupancy: Option<Occupancy>| {
+                occupancy.map_or(String::from("-"), |o| format!("{:.2}", o.average))
+            };
+            writeln!(
+                file,
+                "  {:>10} {:>6.3} {:>11} {:>11} {:>11} {:>6} {:>6} {:>6}",
+                window.clocks.start,
+                window.ipc(),
+                window
+                    .branches
+                    .map_or(String::from("-"), |(n, m)| percent(m, n)),
+                window
+                    .dcache
+                    .map_or(String::from("-"), |(h, n)| percent(h, n)),
+                window
+                    .icache
+                    .map_or(String::from("-"), |(h, n)| percent(h, n)),
+                avg(window.rob),
+                avg(window.rs),
+                avg(window.sq),
+            )?;
+        }
+        file.flush()
+    }
+}
+
+/// How full a structure was over some cycles.
+#[derive(Clone, Copy, Debug)]
+pub struct Occupancy {
+    /// valid entries per cycle
+    pub average: f64,
+    pub max: usize,
+    pub capacity: usize,
+}
+
+/// Counters summed over a range of cycles. Anything the design doesn't have is `None`.
+#[derive(Clone, Debug)]
+pub struct Summary {
+    pub cycles: usize,
+    pub clocks: Range<usize>,
+    pub retired: usize,
+    /// (resolved, mispredicted)
+    pub branches: Option<(usize, usize)>,
+    /// (hits, accesses)
+    pub dcache: Option<(usize, usize)>,
+    pub icache: Option<(usize, usize)>,
+    pub dcache_mshrs: Option<Occupancy>,
+    pub icache_mshrs: Option<Occupancy>,
+    pub rob: Option<Occupancy>,
+    pub rs: Option<Occupancy>,
+    pub sq: Option<Occupancy>,
+}
+
+/// `part` as a percentage of `whole`, or `-` if there's nothing to divide by.
+pub fn percent(part: usize, whole: usize) -> String {
+    if whole == 0 {
+        return String::from("-");
+    }
+    format!("{:.1}%", 100.0 * part as f64 / whole as f64)
+}
+
+impl Summary {
+    pub fn ipc(&self) -> f64 {
+        self.retired as f64 / self.cycles.max(1) as f64
+    }
+
+    /// (name, value) of every counter the design has, in display order. Summaries of the same
+    /// trace always have the same rows.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = vec![
+            ("cycles", self.cycles.to_string()),
+            ("retired", self.retired.to_string()),
+            ("IPC", format!("{:.3}", self.ipc())),
+            match self.retired {
+                0 => ("CPI", String::from("-")),
+                retired => ("CPI", format!("{:.3}", self.cycles as f64 / retired as f64)),
+            },
+        ];
+        if let Some((branches, mispredicts)) = self.branches {
+            rows.push(("branches", branches.to_string()));
+            rows.push((
+                "mispredicts",
+                format!("{mispredicts} ({})", percent(mispredicts, branches)),
+            ));
+        }
+
+        let hit_rate = |(hits, accesses): (usize, usize)| {
+            format!("{hits}/{accesses} ({})", percent(hits, accesses))
+        };
+        let occupancy =
+            |o: Occupancy| format!("avg {:.2}, max {} of {}", o.average, o.max, o.capacity);
+        let optional = [
+            ("dcache hits", self.dcache.map(hit_rate)),
+            ("dcache MSHRs", self.dcache_mshrs.map(occupancy)),
+            ("icache hits", self.icache.map(hit_rate)),
+            ("icache MSHRs", self.icache_mshrs.map(occupancy)),
+            ("ROB occupancy", self.rob.map(occupancy)),
+            ("RS occupancy", self.rs.map(occupancy)),
+            ("SQ occupancy", self.sq.map(occupancy)),
+        ];
+        rows.extend(
+            optional
+                .into_iter()
+                .filter_map(|(name, value)| Some((name, value?))),
+        );
+        rows
+    }
+}
@@ -0,0 +1,187 @@
+//! How well the branch predictor did, from every branch the branch FUs resolved.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::{
+    snapshots::{Snapshots, VerilogValue},
+    structures::nearest_marker,
+    utils::{get_known, indexed},
+};
+
+/// A branch resolved by a branch FU.
+#[derive(Clone, Copy, Debug)]
+pub struct Outcome {
+    /// as a position in [`Snapshots::cycle_indices`]
+    pub cycle: usize,
+    /// clock count of `cycle`
+    pub clock: usize,
+    pub pc: usize,
+    pub taken: bool,
+    pub target_pc: usize,
+    pub pht_index: Option<usize>,
+    /// whether the branch stack squashed the branch's `bid` when it resolved
+    pub mispredicted: bool,
+    /// whether the BTB held the branch with its target when it resolved, for taken branches in
+    /// designs with a BTB. The BTB may have changed since the branch was fetched, so this is only
+    /// an estimate of what fetch saw.
+    pub btb_hit: Option<bool>,
+}
+
+/// Everything about one static branch.
+#[derive(Clone, Debug, Default)]
+pub struct BranchStats {
+    pub pc: usize,
+    pub executed: usize,
+    pub taken: usize,
+    pub mispredicted: usize,
+    /// (hits, lookups) of taken executions in the BTB
+    pub btb: (usize, usize),
+    pub pht_indices: BTreeSet<usize>,
+    /// other branches that used any of the same PHT entries
+    pub aliases: BTreeSet<usize>,
+}
+
+impl BranchStats {
+    /// Fraction of executions that were predicted correctly.
+    pub fn accuracy(&self) -> f64 {
+        1.0 - self.mispredicted as f64 / self.executed.max(1) as f64
+    }
+}
+
+/// Every branch outcome in the trace, and the same grouped by PC.
+pub struct BranchHistory {
+    /// in the order they resolved
+    pub outcomes: Vec<Outcome>,
+    /// sorted by PC
+    pub branches: Vec<BranchStats>,
+    /// PHT index -> branches that used it
+    pub pht: BTreeMap<usize, BTreeSet<usize>>,
+}
+
+impl BranchHistory {
+    /// Collects the branch outputs of the FU at `base`, checking them against the nearest branch
+    /// stack and BTB.
+    pub fn extract(base: &str, snapshots: &mut Snapshots) -> Self {
+        let packets = indexed(&format!("{base}.branch_output_packets"), snapshots);
+        let bstack = nearest_marker(&snapshots.header, "bstack", base);
+        let btb = nearest_marker(&snapshots.header, "btb", base)
+            .map(|btb| indexed(&format!("{btb}.entries"), snapshots));
+
+        let cycle_indices = snapshots.cycle_indices();
+        let original = snapshots.index();
+        let squashes: Vec<usize> = cycle_indices
+            .iter()
+            .map(|&index| {
+                snapshots.go_to(index);
+                bstack
+                    .as_ref()
+                    .and_then(|bstack| get_known(&format!("{bstack}.bmask_squash"), snapshots))
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let mut outcomes = Vec::new();
+        for (cycle, &index) in cycle_indices.iter().enumerate() {
+            snapshots.go_to(index);
+            // a mispredict can be squashed the cycle it resolves or the one after
+            let squashed = squashes[cycle] | squashes.get(cycle + 1).copied().unwrap_or(0);
+
+            for packet in packets.iter() {
+                let valid = snapshots
+                    .get_var(&format!("{packet}.valid"))
+                    .is_some_and(VerilogValue::is_high);
+                if !valid {
+                    continue;
+                }
+                let (Some(pc), Some(taken), Some(target_pc)) = (
+                    get_known(&format!("{packet}.pc"), snapshots),
+                    get_known(&format!("{packet}.taken"), snapshots),
+                    get_known(&format!("{packet}.target_pc"), snapshots),
+                ) else {
+                    continue;
+                };
+
+                let bid = get_known(&format!("{packet}.bid"), snapshots).unwrap_or(0);
+                let taken = taken != 0;
+                let btb_hit = btb.as_ref().filter(|_| taken).map(|entries| {
+                    entries.iter().any(|entry| {
+                        snapshots
+                            .get_var(&format!("{entry}.valid"))
+                            .is_some_and(VerilogValue::is_high)
+                            && get_known(&format!("{entry}.pc"), snapshots) == Some(pc)
+                            && get_known(&format!("{entry}.target_pc"), snapshots)
+                                == Some(target_pc)
+                    })
+                });
+                outcomes.push(Outcome {
+                    cycle,
+                    clock: snapshots.get_clock_count(),
+                    pc,
+                    taken,
+                    target_pc,
+                    pht_index: get_known(&format!("{packet}.branch_packet.pht_index"), snapshots),
+                    mispredicted: bid & squashed != 0,
+                    btb_hit,
+                });
+            }
+        }
+        snapshots.go_to(original);
+
+        let mut by_pc: BTreeMap<usize, BranchStats> = BTreeMap::new();
+        let mut pht: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+        for outcome in outcomes.iter() {
+            let stats = by_pc.entry(outcome.pc).or_insert_with(|| BranchStats {
+                pc: outcome.pc,
+                ..Default::default()
+            });
+            stats.executed += 1;
+            stats.taken += outcome.taken as usize;
+            stats.mispredicted += outcome.mispredicted as usize;
+            if let Some(hit) = outcome.btb_hit {
+                stats.btb.0 += hit as usize;
+                stats.btb.1 += 1;
+            }
+            if let Some(index) = outcome.pht_index {
+                stats.pht_indices.insert(index);
+                pht.entry(index).or_default().insert(outcome.pc);
+            }
+        }
+
+        let mut aliases: HashMap<usize, BTreeSet<usize>> = HashMap::new();
+        for pcs in pht.values() {
+            for &pc in pcs {
+                aliases
+                    .entry(pc)
+                    .or_default()
+                    .extend(pcs.iter().filter(|&&other| other != pc));
+            }
+        }
+        let branches = by_pc
+            .into_values()
+            .map(|mut stats| {
+                stats.aliases = aliases.remove(&stats.pc).unwrap_or_default();
+                stats
+            })
+            .collect();
+
+        Self {
+            outcomes,
+            branches,
+            pht,
+        }
+    }
+
+    /// Cycle of the first mispredict after `cycle` (or the last one before it if `forward` is
+    /// false), only counting the branch at `pc` if given.
+    pub fn next_mispredict(&self, cycle: usize, pc: Option<usize>, forward: bool) -> Option<usize> {
+        let mut mispredicts = self
+            .outcomes
+            .iter()
+            .filter(|outcome| outcome.mispredicted && pc.is_none_or(|pc| outcome.pc == pc))
+            .map(|outcome| outcome.cycle);
+        if forward {
+            mispredicts.find(|&c| c > cycle)
+        } else {
+            mispredicts.rev().find(|&c| c < cycle)
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! The program the testbench loaded into memory.
+use std::{collections::BTreeMap, fs, io, iter, ops::Range};
+
+use elf::{
+    abi::{PF_X, PT_LOAD, STT_FUNC, STT_NOTYPE, STT_OBJECT},
+    endian::AnyEndian,
+    ElfBytes,
+};
+
+use crate::utils::IsaConfig;
+
+/// A function or data object in an ELF's symbol table.
+#[derive(Clone, Debug)]
+struct Symbol {
+    addr: usize,
+    size: usize,
+    name: String,
+}
+
+/// An ELF's symbol table, sorted by address.
+#[derive(Clone, Default)]
+pub struct Symbols {
+    symbols: Vec<Symbol>,
+}
+
+impl Symbols {
+    fn from_elf(file: &ElfBytes<AnyEndian>) -> Result<Self, elf::ParseError> {
+        let Some((table, strings)) = file.symbol_table()? else {
+            return Ok(Self::default());
+        };
+
+        let mut symbols = Vec::new();
+        for symbol in table.iter() {
+            let kind = symbol.st_symtype();
+            if symbol.is_undefined() || ![STT_FUNC, STT_OBJECT, STT_NOTYPE].contains(&kind) {
+                continue;
+            }
+            let name = strings.get(symbol.st_name as usize)?;
+            // skip the assembler's local labels and mapping symbols
+            if name.is_empty() || name.starts_with(".L") || name.starts_with('$') {
+                continue;
+            }
+            symbols.push(Symbol {
+                addr: symbol.st_value as usize,
+                size: symbol.st_size as usize,
+                name: name.to_owned(),
+            });
+        }
+        // sized symbols first at the same address, since they're the ones that say how far they go
+        symbols.sort_by_key(|symbol| (symbol.addr, symbol.size == 0));
+        symbols.dedup_by_key(|symbol| symbol.addr);
+
+        Ok(Self { symbols })
+    }
+
+    /// The symbol `addr` is in, with the offset into it if it isn't the start. Symbols without a
+    /// size cover everything up to the next one.
+    pub fn lookup(&self, addr: usize) -> Option<String> {
+        let i = self.symbols.partition_point(|symbol| symbol.addr <= addr);
+        let symbol = &self.symbols[i.checked_sub(1)?];
+        let offset = addr - symbol.addr;
+        if symbol.size != 0 && offset >= symbol.size {
+            return None;
+        }
+
+        Some(if offset == 0 {
+            symbol.name.clone()
+        } else {
+            format!("{}+{offset:#x}", symbol.name)
+        })
+    }
+}
+
+/// Initial contents of memory, byte by byte. Bytes that weren't loaded read as 0.
+#[derive(Clone, Default)]
+pub struct MemoryImage {
+    bytes: BTreeMap<usize, u8>,
+    /// address ranges holding instructions
+    pub code: Vec<Range<usize>>,
+    /// empty unless it was loaded from an ELF with a symbol table
+    pub symbols: Symbols,
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl MemoryImage {
+    /// Loads an ELF, or a `$readmemh`-style hex file like the `program.mem` the testbench reads.
+    pub fn load(path: &str) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        if data.starts_with(b"\x7fELF") {
+            return Self::from_elf(&data).map_err(|e| invalid_data(format!("{path}: {e}")));
+        }
+
+        let text = String::from_utf8_lossy(&data);
+        Self::from_hex(&text)
+            .ok_or_else(|| invalid_data(format!("{path} isn't an ELF or memory hex file")))
+    }
+
+    /// Loads every `PT_LOAD` segment, zero-filling past the end of the file data like a loader
+    /// would. Executable segments are code.
+    fn from_elf(data: &[u8]) -> Result<Self, elf::ParseError> {
+        let file = ElfBytes::<AnyEndian>::minimal_parse(data)?;
+        let mut image = Self::default();
+
+        for segment in file.segments().into_iter().flatten() {
+            if segment.p_type != PT_LOAD {
+                continue;
+            }
+            let addr = segment.p_vaddr as usize;
+            let contents = file.segment_data(&segment)?;
+            for i in 0..segment.p_memsz as usize {
+                image
+                    .bytes
+                    .insert(addr + i, contents.get(i).copied().unwrap_or(0));
+            }
+            if segment.p_flags & PF_X != 0 {
+                image.code.push(addr..addr + segment.p_filesz as usize);
+            }
+        }
+
+        image.symbols = Symbols::from_elf(&file)?;
+        Ok(image)
+    }
+
+    /// Parses `$readmemh` syntax: whitespace-separated little-endian words, with `@<addr>` to
+    /// jump to a word address. The word size is taken from the width of the first word.
+    fn from_hex(text: &str) -> Option<Self> {
+        let mut bytes = BTreeMap::new();
+        let mut word_size = None;
+        let mut word_addr = 0;
+
+        for token in text
+            .lines()
+            .map(|line| line.split("//").next().unwrap_or(""))
+            .flat_map(str::split_whitespace)
+        {
+            if let Some(addr) = token.strip_prefix('@') {
+                word_addr = usize::from_str_radix(addr, 16).ok()?;
+                continue;
+            }
+
+            let token = token.replace('_', "");
+            let size = *word_size.get_or_insert(token.len().div_ceil(2));
+            let word = u64::from_str_radix(&token, 16).ok()?;
+            for (i, byte) in word.to_le_bytes().into_iter().take(size).enumerate() {
+                bytes.insert(word_addr * size + i, byte);
+            }
+            word_addr += 1;
+        }
+
+        // there's no telling code from data, so everything up to the zero padding at the end is
+        // treated as code
+        let start = *bytes.keys().next()?;
+        let end = bytes
+            .iter()
+            .rev()
+            .find(|(_, &byte)| byte != 0)
+            .map_or(start, |(&addr, _)| addr + 1);
+
+        Some(Self {
+            bytes,
+            code: iter::once(start..end).collect(),
+            symbols: Symbols::default(),
+        })
+    }
+
+    /// Reads `size` bytes little-endian starting at `addr`.
+    pub fn read(&self, addr: usize, size: usize) -> usize {
+        (0..size).rev().fold(0, |value, i| {
+            (value << 8) | *self.bytes.get(&(addr + i)).unwrap_or(&0) as usize
+        })
+    }
+
+    /// Writes the low `size` bytes of `value` little-endian starting at `addr`.
+    pub fn write(&mut self, addr: usize, size: usize, value: usize) {
+        for i in 0..size {
+            self.bytes.insert(addr + i, (value >> (8 * i)) as u8);
+        }
+    }
+
+    /// Every address that was loaded, in order.
+    pub fn addrs(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bytes.keys().copied()
+    }
+
+    /// Addresses of every instruction in the code ranges, in order, as `isa` lays them out.
+    pub fn inst_addrs(&self, isa: &IsaConfig) -> Vec<usize> {
+        let mut addrs = Vec::new();
+        for range in self.code.iter() {
+            let mut addr = range.start;
+            while addr < range.end {
+                addrs.push(addr);
+                addr += isa.inst_len(self.read(addr, 4));
+            }
+        }
+        addrs
+    }
+}
@@ -0,0 +1,440 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+};
+
+use crate::{
+    lifetime::{Fate, Pipeline, Trace},
+    snapshots::{Snapshots, VerilogValue},
+    structures::nearest_marker,
+    utils::{get_known, indexed, parse_inst},
+};
+
+/// One architecturally committed instruction.
+#[derive(Clone, Debug)]
+pub struct Retired {
+    /// cycle it retired in, as a position in [`Snapshots::cycle_indices`]
+    pub cycle: usize,
+    /// clock count of `cycle`
+    pub clock: usize,
+    pub pc: usize,
+    pub disasm: String,
+    /// register written, `None` for instructions that don't write one (or write x0)
+    pub rd: Option<usize>,
+    pub value: usize,
+    /// memory it wrote, if it's a store and the trace says where
+    pub store: Option<Store>,
+}
+
+impl Retired {
+    /// What this instruction did architecturally, to compare against a reference.
+    pub fn commit(&self) -> Commit {
+        Commit {
+            pc: self.pc,
+            write: self.rd.map(|rd| (rd, self.value)),
+            store: self.store,
+        }
+    }
+
+    /// The line the testbench would write to `writeback.out` for this instruction.
+    pub fn writeback_line(&self) -> String {
+        // the testbench doesn't log stores
+        Commit {
+            store: None,
+            ..self.commit()
+        }
+        .to_string()
+    }
+}
+
+/// A write to memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Store {
+    pub addr: usize,
+    /// in bytes
+    pub size: usize,
+    /// only the low `size` bytes are kept
+    pub value: usize,
+}
+
+impl Store {
+    pub fn new(addr: usize, size: usize, value: usize) -> Self {
+        let mask = if size >= 8 { !0 } else { (1 << (8 * size)) - 1 };
+        Self {
+            addr,
+            size,
+            value: value & mask,
+        }
+    }
+}
+
+/// The architectural effect of one committed instruction: its PC, the register it wrote and the
+/// memory it wrote.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Commit {
+    pub pc: usize,
+    /// (register, value), `None` if it didn't write one
+    pub write: Option<(usize, usize)>,
+    /// `None` if it didn't write memory or the log doesn't say
+    pub store: Option<Store>,
+}
+
+impl Commit {
+    /// Parses a line of either a testbench `writeback.out` (`PC=00000004, REG[ 2]=00000001`) or a
+    /// Spike commit log (`core   0: 3 0x00000004 (0x00100113) x2  0x00000001`).
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("PC=") {
+            let (pc, write) = rest.split_once(',')?;
+            let pc = usize::from_str_radix(pc.trim(), 16).ok()?;
+            let write = write.trim();
+            if write == "---" {
+                return Some(Self {
+                    pc,
+                    write: None,
+                    store: None,
+                });
+            }
+            let (reg, value) = write.strip_prefix("REG[")?.split_once("]=")?;
+            let reg = reg.trim().parse().ok()?;
+            let value = usize::from_str_radix(value.trim(), 16).ok()?;
+            return Some(Self {
+                pc,
+                write: Some((reg, value)).filter(|&(reg, _)| reg != 0),
+                store: None,
+            });
+        }
+
+        let (_, rest) = line.strip_prefix("core")?.split_once(':')?;
+        let mut tokens = rest.split_whitespace();
+        let _privilege = tokens.next()?;
+        let pc = parse_hex(tokens.next()?)?;
+        let _inst = tokens.next()?;
+        let mut write = None;
+        let mut store = None;
+        while let (Some(kind), Some(value)) = (tokens.next(), tokens.next()) {
+            if kind == "mem" {
+                // stores are `mem <addr> <value>`, with the value as wide as the store
+                let Some(data) = tokens.next() else {
+                    break;
+                };
+                let size = data.trim_start_matches("0x").len() / 2;
+                store = Some(Store::new(parse_hex(value)?, size, parse_hex(data)?));
+            } else if let Some(reg) = kind.strip_prefix('x') {
+                write = Some((reg.parse().ok()?, parse_hex(value)?));
+            }
+        }
+        Some(Self {
+            pc,
+            write: write.filter(|&(reg, _)| reg != 0),
+            store,
+        })
+    }
+
+    /// Whether `other` did the same thing. Stores are only compared if both sides know about them.
+    pub fn matches(&self, other: &Commit) -> bool {
+        self.pc == other.pc
+            && self.write == other.write
+            && (self.store.is_none() || other.store.is_none() || self.store == other.store)
+    }
+}
+
+impl fmt::Display for Commit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.write {
+            Some((rd, value)) => write!(f, "PC={:08x}, REG[{rd:2}]={value:08x}", self.pc)?,
+            None => write!(f, "PC={:08x}, ---", self.pc)?,
+        }
+        match self.store {
+            Some(store) => write!(
+                f,
+                ", MEM[{:08x}]={:0width$x}",
+                store.addr,
+                store.value,
+                width = store.size * 2
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_hex(s: &str) -> Option<usize> {
+    usize::from_str_radix(s.trim_start_matches("0x"), 16).ok()
+}
+
+/// Reads every committed instruction out of a reference log, skipping lines that aren't commits.
+pub fn load_reference(path: &str) -> io::Result<Vec<Commit>> {
+    let reference: Vec<Commit> = fs::read_to_string(path)?
+        .lines()
+        .filter_map(Commit::parse)
+        .collect();
+    if reference.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{path} doesn't look like a writeback or commit log"),
+        ));
+    }
+    Ok(reference)
+}
+
+/// The first place the retirement stream stopped agreeing with the reference.
+pub struct Divergence<'a> {
+    /// position in program order
+    pub index: usize,
+    /// what the reference committed, `None` if the trace retired more instructions than it has
+    pub expected: Option<Commit>,
+    pub actual: &'a Retired,
+}
+
+impl fmt::Display for Divergence<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let actual = self.actual.commit();
+        match self.expected {
+            Some(expected) => write!(
+                f,
+                "Instruction {} (clock {}) diverged from the reference: expected {expected}, got {actual}",
+                self.index, self.actual.clock
+            ),
+            None => write!(
+                f,
+                "Instruction {} (clock {}) retired past the end of the reference: {actual}",
+                self.index, self.actual.clock
+            ),
+        }
+    }
+}
+
+/// Entries of a log lined up with a reference when the trace doesn't start at reset.
+const ALIGN_LEN: usize = 16;
+
+/// Every committed instruction in the trace, in program order.
+pub struct RetireLog {
+    pub entries: Vec<Retired>,
+}
+
+/// Bytes written by a store instruction, `None` if it isn't one.
+fn store_size(inst_bits: usize) -> Option<usize> {
+    if inst_bits & 0x7f != 0b010_0011 {
+        return None;
+    }
+    Some(1 << ((inst_bits >> 12) & 0b11))
+}
+
+/// The store the store queue at `store_queue` is about to write for ROB entry `rob_num`, which
+/// stores retire from the head of.
+fn retiring_store(
+    store_queue: &str,
+    rob_num: usize,
+    size: usize,
+    snapshots: &Snapshots,
+) -> Option<Store> {
+    let head = get_known(&format!("{store_queue}.head"), snapshots)?;
+    let entry = format!("{store_queue}.entries[{head}]");
+    if get_known(&format!("{entry}.rob_num"), snapshots)? != rob_num {
+        return None;
+    }
+    Some(Store::new(
+        get_known(&format!("{entry}.addr"), snapshots)?,
+        size,
+        get_known(&format!("{entry}.data"), snapshots)?,
+    ))
+}
+
+impl RetireLog {
+    /// Whether there's a retirement stream at `base`, i.e. it's a ROB or a testbench with commit
+    /// ports.
+    pub fn available(base: &str, snapshots: &Snapshots) -> bool {
+        snapshots
+            .get_var(&format!("{base}.dbg_this_is_rob"))
+            .is_some()
+            || snapshots
+                .get_scope(&format!("{base}.committed_insts[0]"))
+                .is_some()
+    }
+
+    /// Reconstructs what retired out of the ROB at (or closest to) `base`, reading written
+    /// values from the regfile and stores from the store queue.
+    pub fn from_rob(base: &str, snapshots: &mut Snapshots) -> Option<Self> {
+        let pipeline = Pipeline::new(base, snapshots)?;
+        let regfile = nearest_marker(&snapshots.header, "regfile", &pipeline.rob);
+        let store_queue = nearest_marker(&snapshots.header, "store_queue", &pipeline.rob);
+
+        let cycle_indices = snapshots.cycle_indices();
+        let trace = Trace::build(&pipeline, snapshots, &cycle_indices, 0..cycle_indices.len());
+
+        let original = snapshots.index();
+        let mut entries = Vec::new();
+
+        for traced in trace.insts.iter() {
+            if traced.fate != Fate::Retired {
+                continue;
+            }
+            let cycle = traced.end();
+            snapshots.go_to(cycle_indices[cycle]);
+
+            let entry = format!("{}.entries[{}]", pipeline.rob, traced.inst.rob_num);
+            let inst_bits = get_known(&format!("{entry}.info.inst.inst"), snapshots);
+            let rd = inst_bits
+                .and_then(|bits| snapshots.context.inst_rd(bits))
+                .filter(|&rd| rd != 0);
+            let value = match (&regfile, rd) {
+                (Some(regfile), Some(_)) => {
+                    let key = format!("{regfile}.regfile_mem.memData[{}]", traced.inst.dest_tag);
+                    get_known(&key, snapshots).unwrap_or(0)
+                }
+                _ => 0,
+            };
+            let store = match (&store_queue, inst_bits.and_then(store_size)) {
+                (Some(store_queue), Some(size)) => {
+                    retiring_store(store_queue, traced.inst.rob_num, size, snapshots)
+                }
+                _ => None,
+            };
+
+            entries.push(Retired {
+                cycle,
+                clock: snapshots.get_clock_count(),
+                pc: traced.inst.pc,
+                disasm: parse_inst(&format!("{entry}.info.inst"), snapshots),
+                rd,
+                value,
+                store,
+            });
+        }
+
+        snapshots.go_to(original);
+        Some(Self { entries })
+    }
+
+    /// Reads the testbench's own `committed_insts[]` under `base`, for designs without a ROB such
+    /// as the in-order pipeline. Commits are in program order, so without a `PC` on the ports each
+    /// one's PC is the NPC of the one before it; only the trace's first commit is taken to be a
+    /// 4-byte instruction that didn't jump.
+    pub fn from_testbench(base: &str, snapshots: &mut Snapshots) -> Option<Self> {
+        let ports = indexed(&format!("{base}.committed_insts"), snapshots);
+        if ports.is_empty() {
+            return None;
+        }
+
+        let original = snapshots.index();
+        let mut entries = Vec::new();
+        let mut next_pc = None;
+
+        for (cycle, index) in snapshots.cycle_indices().into_iter().enumerate() {
+            snapshots.go_to(index);
+            for port in ports.iter() {
+                let valid = snapshots
+                    .get_var(&format!("{port}.valid"))
+                    .is_some_and(VerilogValue::is_high);
+                let Some(npc) = get_known(&format!("{port}.NPC"), snapshots).filter(|_| valid)
+                else {
+                    continue;
+                };
+                let pc = get_known(&format!("{port}.PC"), snapshots)
+                    .or(next_pc)
+                    .unwrap_or(npc.wrapping_sub(4));
+                next_pc = Some(npc);
+
+                entries.push(Retired {
+                    cycle,
+                    clock: snapshots.get_clock_count(),
+                    pc,
+                    disasm: String::new(),
+                    rd: get_known(&format!("{port}.reg_idx"), snapshots).filter(|&rd| rd != 0),
+                    value: get_known(&format!("{port}.data"), snapshots).unwrap_or(0),
+                    store: None,
+                });
+            }
+        }
+
+        snapshots.go_to(original);
+        Some(Self { entries })
+    }
+
+    /// Retirement stream of the core the structure at `base` is in, or of the testbench at `base`
+    /// if there's no ROB.
+    pub fn extract_at(base: &str, snapshots: &mut Snapshots) -> Option<Self> {
+        Self::from_rob(base, snapshots).or_else(|| Self::from_testbench(base, snapshots))
+    }
+
+    /// Retirement stream of the first core, or of the testbench if there's no ROB.
+    pub fn extract(snapshots: &mut Snapshots) -> Option<Self> {
+        let base = snapshots.get_base();
+        Self::extract_at(&base, snapshots)
+    }
+
+    /// How many of `reference`'s commits come before the log's first entry, for traces that start
+    /// partway through the program. They're lined up by the PCs of the first few entries, since
+    /// one PC can come up many times in a loop. `None` if the log's start isn't in the reference.
+    pub fn offset_in(&self, reference: &[Commit]) -> Option<usize> {
+        let pcs: Vec<usize> = self.entries.iter().take(ALIGN_LEN).map(|e| e.pc).collect();
+        (0..=reference.len().saturating_sub(pcs.len())).find(|&skipped| {
+            reference[skipped..]
+                .iter()
+                .map(|commit| commit.pc)
+                .take(pcs.len())
+                .eq(pcs.iter().copied())
+        })
+    }
+
+    /// Compares the log with a reference in program order, starting `skipped` commits into the
+    /// reference (see [`Self::offset_in`]). Running out of trace before the reference ends isn't a
+    /// divergence, since the trace may have been cut short.
+    pub fn diff(&self, reference: &[Commit], skipped: usize) -> Option<Divergence<'_>> {
+        let reference = reference.get(skipped..).unwrap_or_default();
+        self.entries.iter().enumerate().find_map(|(index, actual)| {
+            let expected = reference.get(index).copied();
+            let matches = expected.is_some_and(|expected| expected.matches(&actual.commit()));
+            (!matches).then_some(Divergence {
+                index: skipped + index,
+                expected,
+                actual,
+            })
+        })
+    }
+
+    /// Writes the log in the same format as the testbench's `writeback.out`.
+    pub fn write_writeback(&self, path: &str) -> io::Result<()> {
+        let mut file = BufWriter::new(File::create(path)?);
+        for entry in self.entries.iter() {
+            writeln!(file, "{}", entry.writeback_line())?;
+        }
+        file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_writeback_lines() {
+        let commit = Commit::parse("PC=00000004, REG[ 2]=00000001").unwrap();
+        assert_eq!(commit.pc, 4);
+        assert_eq!(commit.write, Some((2, 1)));
+        assert_eq!(commit.store, None);
+
+        let commit = Commit::parse("PC=00000008, ---").unwrap();
+        assert_eq!((commit.pc, commit.write), (8, None));
+    }
+
+    #[test]
+    fn parses_spike_lines() {
+        let commit = Commit::parse("core   0: 3 0x00000004 (0x00100113) x2  0x00000001").unwrap();
+        assert_eq!(commit.pc, 4);
+        assert_eq!(commit.write, Some((2, 1)));
+
+        let commit =
+            Commit::parse("core   0: 3 0x0000000c (0x00210023) mem 0x00000100 0x01").unwrap();
+        assert_eq!(commit.pc, 0xc);
+        assert_eq!(commit.write, None);
+        assert_eq!(commit.store, Some(Store::new(0x100, 1, 1)));
+    }
+
+    #[test]
+    fn skips_other_lines() {
+        assert!(Commit::parse("core   0: >>>>  main").is_none());
+        assert!(Commit::parse("@@@ 12 cycles").is_none());
+    }
+}
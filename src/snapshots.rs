@@ -1,11 +1,13 @@
 use im::HashMap;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::fmt::Display;
 use std::io::BufReader;
 use std::ops;
 use std::{fs::File, io};
 use vcd::{self, Header, IdCode, Scope, ScopeItem, Value, Vector};
 
-use crate::utils::DisplayType;
+use crate::utils::{DisplayContext, DisplayType};
 use crate::var_index::VarIndex;
 
 pub enum DifferenceType {
@@ -17,16 +19,35 @@ pub enum DifferenceType {
 pub enum VerilogValue {
     Scalar(Value),
     Vector(Vector),
+    /// stand-in for a signal that isn't in the vcd file, see [`Snapshots::get_var_or_missing`]
+    Missing,
 }
 
+static MISSING: VerilogValue = VerilogValue::Missing;
+
 // todo: honestly just move this struct into a new file
 impl VerilogValue {
-    pub fn format(&self, display_type: &DisplayType) -> String {
+    pub fn format(&self, display_type: &DisplayType, context: &DisplayContext) -> String {
+        if self.is_missing() {
+            return self.to_string();
+        }
         match display_type {
             DisplayType::Binary => self.as_binary(),
             DisplayType::Decimal => self.as_decimal(),
             DisplayType::Hex => self.as_hex(),
-            DisplayType::Custom(format_fn) => format_fn(self),
+            DisplayType::Custom(format_fn) => format_fn(self, context),
+        }
+    }
+
+    /// Hex, followed by the program symbol it points into if there's one, e.g. `0x24 <sum+0x4>`.
+    pub fn as_addr(&self, context: &DisplayContext) -> String {
+        let hex = self.as_hex();
+        if self.is_unknown() || self.is_missing() {
+            return hex;
+        }
+        match context.symbolize(self.as_usize()) {
+            Some(symbol) => format!("{hex} <{symbol}>"),
+            None => hex,
         }
     }
 
@@ -54,6 +75,7 @@ impl VerilogValue {
                 let val: u64 = bits.iter().fold(0, |res, new| (res << 1) + new);
                 format!("{:#x}", val)
             }
+            VerilogValue::Missing => self.to_string(),
         }
     }
 
@@ -77,6 +99,7 @@ impl VerilogValue {
                 let val: u64 = bits.iter().fold(0, |res, new| (res << 1) + new);
                 val.to_string()
             }
+            VerilogValue::Missing => self.to_string(),
         }
     }
 
@@ -99,6 +122,7 @@ impl VerilogValue {
                 let val = bits.iter().fold(0, |res, new| (res << 1) + new);
                 val
             }
+            VerilogValue::Missing => 0,
         }
     }
 
@@ -106,6 +130,7 @@ impl VerilogValue {
         match self {
             VerilogValue::Scalar(value) => matches!(value, Value::V1),
             VerilogValue::Vector(vector) => vector.iter().all(|x| matches!(x, Value::V1)),
+            VerilogValue::Missing => false,
         }
     }
 
@@ -113,6 +138,7 @@ impl VerilogValue {
         match self {
             VerilogValue::Scalar(value) => matches!(value, Value::V0),
             VerilogValue::Vector(vector) => vector.iter().all(|x| matches!(x, Value::V0)),
+            VerilogValue::Missing => false,
         }
     }
 
@@ -120,9 +146,14 @@ impl VerilogValue {
         match self {
             VerilogValue::Scalar(value) => matches!(value, Value::X | Value::Z),
             VerilogValue::Vector(vector) => vector.iter().any(|x| matches!(x, Value::X | Value::Z)),
+            VerilogValue::Missing => true,
         }
     }
 
+    pub fn is_missing(&self) -> bool {
+        matches!(self, VerilogValue::Missing)
+    }
+
     pub fn from_usize(value: usize, size: usize) -> Self {
         if size == 1 {
             match value & 1 {
@@ -153,6 +184,7 @@ impl ops::Add<&VerilogValue> for &VerilogValue {
 
     fn add(self, rhs: &VerilogValue) -> Self::Output {
         match (self, rhs) {
+            (VerilogValue::Missing, _) | (_, VerilogValue::Missing) => VerilogValue::Missing,
             (VerilogValue::Scalar(value1), VerilogValue::Scalar(value2)) => {
                 VerilogValue::Vector(Vector::from([*value1, *value2]))
             }
@@ -184,6 +216,9 @@ impl Display for VerilogValue {
             VerilogValue::Vector(vector) => {
                 write!(f, "{}", vector)
             }
+            VerilogValue::Missing => {
+                write!(f, "<missing>")
+            }
         }
     }
 }
@@ -199,7 +234,11 @@ pub struct Snapshots {
     shots: Vec<Snapshot>,
     var_index: VarIndex,
     pub header: Header,
+    /// how instructions and addresses in the trace are decoded and shown
+    pub context: DisplayContext,
     index: usize,
+    /// signals asked for through `get_var_or_missing` that weren't found, drained by `take_missing`
+    missing: RefCell<BTreeSet<String>>,
 }
 
 pub fn get_header_base(header: &Header) -> String {
@@ -281,7 +320,9 @@ impl Snapshots {
             shots,
             var_index,
             header,
+            context: DisplayContext::default(),
             index,
+            missing: RefCell::new(BTreeSet::new()),
         })
     }
 
@@ -334,6 +375,42 @@ impl Snapshots {
         self.index = self.shots.len() - 1;
     }
 
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn go_to(&mut self, index: usize) {
+        self.index = index.min(self.shots.len() - 1);
+    }
+
+    /// Index of the last snapshot in each clock cycle, i.e. once everything in it has settled.
+    pub fn cycle_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = Vec::new();
+        for (i, shot) in self.shots.iter().enumerate() {
+            match indices.last_mut() {
+                Some(last) if self.shots[*last].clock_count == shot.clock_count => *last = i,
+                _ => indices.push(i),
+            }
+        }
+        indices
+    }
+
+    /// Position of the current snapshot's cycle in [`Snapshots::cycle_indices`].
+    pub fn cycle(&self) -> usize {
+        // every clock edge gets its own timestamp, so clock counts don't skip any cycles
+        self.shots[self.index].clock_count - self.shots[0].clock_count
+    }
+
+    /// The testbench's own clock counter if it has one, otherwise the number of clock edges seen.
+    pub fn get_clock_count(&self) -> usize {
+        let tb_clock_count_base = format!("{}.clock_count", self.get_base());
+        if let Some(derived_clock_count) = self.get_var(&tb_clock_count_base) {
+            derived_clock_count.as_usize()
+        } else {
+            self.shots[self.index].clock_count
+        }
+    }
+
     pub fn get_base(&self) -> String {
         get_header_base(&self.header)
     }
@@ -343,6 +420,20 @@ impl Snapshots {
         self.shots[self.index].variables.get(&code)
     }
 
+    /// Like `get_var`, but returns [`VerilogValue::Missing`] instead of `None` and remembers the
+    /// name so views can report which signals they couldn't find.
+    pub fn get_var_or_missing(&self, var_name: &str) -> &VerilogValue {
+        self.get_var(var_name).unwrap_or_else(|| {
+            self.missing.borrow_mut().insert(var_name.to_owned());
+            &MISSING
+        })
+    }
+
+    /// Returns (and forgets) every name `get_var_or_missing` couldn't find since the last call.
+    pub fn take_missing(&self) -> Vec<String> {
+        self.missing.take().into_iter().collect()
+    }
+
     pub fn get_scope(&self, scope_name: &str) -> Option<&Scope> {
         let name_list: Vec<_> = scope_name.split('.').collect();
         self.header.find_scope(name_list.as_slice())
@@ -351,4 +442,9 @@ impl Snapshots {
     pub fn autocomplete_var(&self, var_name: &str) -> Vec<String> {
         self.var_index.engine.search(var_name)
     }
+
+    /// Like [`Self::autocomplete_var`], but only offers scopes that can be shown as a table.
+    pub fn autocomplete_table(&self, scope_name: &str) -> Vec<String> {
+        self.var_index.tables.search(scope_name)
+    }
 }
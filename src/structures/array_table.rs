@@ -0,0 +1,194 @@
+use std::cmp::max;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Cell, Row, StatefulWidget, Table, Widget},
+};
+use vcd::{Scope, ScopeItem};
+
+use crate::{snapshots::Snapshots, utils::_path_predecessor};
+
+use super::{Discovery, StructureView};
+
+/// A set of sibling scopes that only differ in their index, e.g. `entries[0]`, `entries[1]`, ...
+#[derive(Clone, Debug)]
+struct ArrayGroup {
+    name: String,
+    indices: Vec<String>,
+    fields: Vec<String>,
+}
+
+/// Generic table for any scope holding an array of packed structs. Columns are the fields of
+/// the first element, rows are the elements.
+#[derive(Clone, Debug)]
+pub struct ArrayTable {
+    base: String,
+    /// scope the array elements live in
+    parent: String,
+    groups: Vec<ArrayGroup>,
+}
+
+/// Splits `entries[3]` into (`entries`, `[3]`) and `metadata[0][1]` into (`metadata`, `[0][1]`).
+fn split_index(identifier: &str) -> Option<(&str, &str)> {
+    let start = identifier.find('[')?;
+    if start == 0 || !identifier.ends_with(']') {
+        return None;
+    }
+    Some(identifier.split_at(start))
+}
+
+/// Every variable below `scope`, as a path relative to `scope`.
+fn collect_fields(prefix: &str, scope: &Scope, fields: &mut Vec<String>) {
+    for item in scope.items.iter() {
+        match item {
+            ScopeItem::Var(var) => fields.push(format!("{prefix}{}", var.reference)),
+            ScopeItem::Scope(sub) => {
+                collect_fields(&format!("{prefix}{}.", sub.identifier), sub, fields)
+            }
+            _ => {}
+        }
+    }
+}
+
+fn collect_groups(scope: &Scope, only: Option<&str>) -> Vec<ArrayGroup> {
+    let mut groups: Vec<ArrayGroup> = Vec::new();
+
+    for item in scope.items.iter() {
+        let ScopeItem::Scope(sub) = item else {
+            continue;
+        };
+        let Some((name, index)) = split_index(&sub.identifier) else {
+            continue;
+        };
+        if only.is_some_and(|only| only != name) {
+            continue;
+        }
+
+        if let Some(group) = groups.iter_mut().find(|g| g.name == name) {
+            group.indices.push(index.to_owned());
+        } else {
+            let mut fields = Vec::new();
+            collect_fields("", sub, &mut fields);
+            groups.push(ArrayGroup {
+                name: name.to_owned(),
+                indices: vec![index.to_owned()],
+                fields,
+            });
+        }
+    }
+
+    groups.retain(|g| !g.fields.is_empty());
+    groups
+}
+
+impl ArrayTable {
+    /// `base` can either be a scope containing arrays (`tb.rob`) or an array itself (`tb.rob.entries`).
+    pub fn new(base: &str, snapshots: &Snapshots) -> Option<Self> {
+        let (parent, groups) = if snapshots.get_scope(&format!("{base}[0]")).is_some() {
+            let parent = _path_predecessor(base);
+            let name = base.rsplit('.').next()?;
+            let groups = collect_groups(snapshots.get_scope(&parent)?, Some(name));
+            (parent, groups)
+        } else {
+            (
+                base.to_owned(),
+                collect_groups(snapshots.get_scope(base)?, None),
+            )
+        };
+
+        if groups.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            base: base.to_owned(),
+            parent,
+            groups,
+        })
+    }
+
+    fn get_group_table<'a>(&self, group: &'a ArrayGroup, snapshots: &Snapshots) -> Table<'a> {
+        let mut widths = vec![1];
+        widths.extend(group.fields.iter().map(|x| x.len() as u16));
+
+        let mut header = vec!["#"];
+        header.extend(group.fields.iter().map(String::as_str));
+        let header = Row::new(header).bold().on_blue();
+
+        let mut rows = Vec::with_capacity(group.indices.len());
+
+        for index in group.indices.iter() {
+            let row_base = format!("{}.{}{index}", self.parent, group.name);
+            let mut is_valid = true;
+
+            let label = index.trim_start_matches('[').trim_end_matches(']');
+            let label = label.replace("][", ",");
+            widths[0] = max(widths[0], label.len() as u16);
+            let mut row_cells = vec![Cell::new(label)];
+
+            for (j, field) in group.fields.iter().enumerate() {
+                let value = snapshots.get_var_or_missing(&format!("{row_base}.{field}"));
+                if field == "valid" && !value.is_high() {
+                    is_valid = false;
+                }
+                let string = value.as_hex();
+
+                widths[j + 1] = max(widths[j + 1], string.len() as u16);
+                row_cells.push(Cell::new(string));
+            }
+
+            let mut row = Row::new(row_cells);
+            if !is_valid {
+                row = row.dim();
+            }
+            rows.push(row);
+        }
+
+        let title = Line::from(format!("{}[]", group.name)).bold().centered();
+        Table::new(rows, widths)
+            .header(header)
+            .block(Block::bordered().title(title))
+    }
+}
+
+impl StatefulWidget for ArrayTable {
+    type State = Snapshots;
+
+    fn render(self, area: Rect, buf: &mut Buffer, snapshots: &mut Self::State) {
+        let title = Line::from(self.base.clone()).bold().centered();
+        let block = Block::bordered().title(title);
+        let inner_area = block.inner(area);
+        Widget::render(block, area, buf);
+
+        let constraints = self
+            .groups
+            .iter()
+            .map(|g| Constraint::Length((g.indices.len() + 3) as u16));
+        let areas = Layout::vertical(constraints).split(inner_area);
+
+        for (group, area) in self.groups.iter().zip(areas.iter()) {
+            Widget::render(self.get_group_table(group, snapshots), *area, buf);
+        }
+    }
+}
+
+impl StructureView for ArrayTable {
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        Self::new(base, discovery.snapshots)
+    }
+
+    fn name(&self) -> String {
+        self.base.clone()
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        StatefulWidget::render(self.clone(), area, buf, snapshots);
+    }
+}
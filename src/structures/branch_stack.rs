@@ -8,7 +8,7 @@ use ratatui::{
 
 use crate::snapshots::Snapshots;
 
-use super::map_table::MapTable;
+use super::{map_table::MapTable, view::impl_structure_view};
 
 #[derive(Clone)]
 pub struct BranchStack {
@@ -26,15 +26,13 @@ impl BranchStack {
         })
     }
 
-    fn get_masks(&self, snapshots: &Snapshots) -> Line {
+    fn get_masks(&self, snapshots: &Snapshots) -> Line<'_> {
         let bmask_clear = snapshots
-            .get_var(&format!("{}.bmask_clear", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.bmask_clear", self.base))
             .as_binary();
 
         let bmask_squash = snapshots
-            .get_var(&format!("{}.bmask_squash", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.bmask_squash", self.base))
             .as_binary();
 
         Line::from(vec![
@@ -65,3 +63,5 @@ impl StatefulWidget for BranchStack {
         StatefulWidget::render(self.map_table, rest, buf, snapshots);
     }
 }
+
+impl_structure_view!(BranchStack, "Branch Stack");
@@ -8,6 +8,8 @@ use ratatui::{
 
 use crate::snapshots::Snapshots;
 
+use super::view::impl_structure_view;
+
 // true if we can use the raw name as the key to index
 const HEADERS: [(&str, bool); 2] = [("pc", true), ("target_pc", true)];
 
@@ -55,18 +57,17 @@ impl StatefulWidget for Btb {
             let mut row_cells: Vec<Cell> = vec![];
             let row_base = format!("{}.entries[{i}]", self.base);
             let is_valid = snapshots
-                .get_var(&format!("{row_base}.valid"))
-                .unwrap()
+                .get_var_or_missing(&format!("{row_base}.valid"))
                 .is_high();
 
             for (j, (name, is_key)) in HEADERS.iter().enumerate() {
                 let string = if *is_key {
                     let full_key = format!("{row_base}.{name}");
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
 
                     // string that gets displayed in the cell section
                     match *name {
-                        "pc" | "target_pc" => value.as_hex(),
+                        "pc" | "target_pc" => value.as_addr(&snapshots.context),
                         _ => {
                             format!("{}", value)
                         }
@@ -97,3 +98,9 @@ impl StatefulWidget for Btb {
         Widget::render(table, area, buf);
     }
 }
+
+impl_structure_view!(Btb, "Branch Target Buffer", {
+    fn height_hint(&self) -> Option<u16> {
+        Some(self.size as u16 + 1 + 2)
+    }
+});
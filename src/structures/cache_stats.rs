@@ -0,0 +1,237 @@
+use std::cell::OnceCell;
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::Marker,
+    text::Line,
+    widgets::{Axis, Block, Cell, Chart, Dataset, GraphType, Paragraph, Row, Table, Widget},
+};
+
+use crate::{
+    cache_stats::{CacheHistory, EventKind},
+    snapshots::Snapshots,
+};
+
+use super::{Discovery, StructureView};
+
+/// Background of heatmap cells from the fewest events to the most; cells with none aren't
+/// colored.
+const HEAT: [Color; 8] = [
+    Color::Indexed(22),
+    Color::Indexed(28),
+    Color::Indexed(64),
+    Color::Indexed(100),
+    Color::Indexed(136),
+    Color::Indexed(166),
+    Color::Indexed(160),
+    Color::Indexed(196),
+];
+/// Width of a way's cell in the heatmap.
+const CELL_WIDTH: u16 = 6;
+const TIMELINE_HEIGHT: u16 = 8;
+
+/// Hits, misses, evictions and writebacks of a cache tallied per set and way over the whole trace,
+/// with misses over time below.
+pub struct CacheStats {
+    base: String,
+    /// extracted the first time it's shown, since it has to go through the whole trace
+    history: OnceCell<CacheHistory>,
+    /// index into [`EventKind::ALL`] of what the heatmap shows, cycled with <M>
+    metric: usize,
+}
+
+impl CacheStats {
+    /// `base` is a dcache, icache or facache.
+    pub fn new(base: &str, snapshots: &Snapshots) -> Option<Self> {
+        if CacheHistory::lines(base, snapshots).is_empty() {
+            return None;
+        }
+        Some(Self {
+            base: base.to_owned(),
+            history: OnceCell::new(),
+            metric: 0,
+        })
+    }
+
+    fn render_heatmap(&self, history: &CacheHistory, current: usize, area: Rect, buf: &mut Buffer) {
+        let kind = EventKind::ALL[self.metric];
+        let counts = history.tally(kind);
+        let max = counts.iter().flatten().copied().max().unwrap_or(0);
+        let touched: Vec<(usize, usize)> = history
+            .events
+            .iter()
+            .filter(|event| event.cycle == current)
+            .map(|event| (event.set, event.way))
+            .collect();
+
+        let cell = |set: usize, way: usize| {
+            let count = counts[set][way];
+            let mut style = Style::new();
+            if count > 0 {
+                let heat = (count * HEAT.len()).div_ceil(max.max(1)) - 1;
+                style = style.bg(HEAT[heat]).fg(Color::White);
+            } else {
+                style = style.dim();
+            }
+            // lines something happened to this cycle
+            if touched.contains(&(set, way)) {
+                style = style.bold().underlined();
+            }
+            Cell::from(format!("{count:>5}")).style(style)
+        };
+
+        // sets are wrapped into side-by-side columns when there are more than fit
+        let rows_per_column = (area.height.saturating_sub(1) as usize).max(1);
+        let num_columns = history.num_sets.div_ceil(rows_per_column);
+        let column_width = 5 + CELL_WIDTH * history.num_ways as u16 + 2;
+        let areas =
+            Layout::horizontal(vec![Constraint::Length(column_width); num_columns]).split(area);
+
+        let mut widths = vec![Constraint::Length(4)];
+        widths.extend(vec![Constraint::Length(CELL_WIDTH - 1); history.num_ways]);
+        let mut header = vec![String::from("set")];
+        header.extend((0..history.num_ways).map(|way| format!("{:>5}", format!("w{way}"))));
+
+        for (column, &area) in areas.iter().enumerate() {
+            let sets =
+                column * rows_per_column..((column + 1) * rows_per_column).min(history.num_sets);
+            let rows = sets.map(|set| {
+                let mut cells = vec![Cell::from(set.to_string())];
+                cells.extend((0..history.num_ways).map(|way| cell(set, way)));
+                Row::new(cells)
+            });
+            Table::new(rows, widths.clone())
+                .header(Row::new(header.clone()).bold().on_blue())
+                .render(area, buf);
+        }
+    }
+
+    fn render_timeline(
+        &self,
+        history: &CacheHistory,
+        current: usize,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let num_cycles = history.clocks.len();
+        // enough cycles per point for the line to fit the chart
+        let bucket = num_cycles.div_ceil(area.width.max(1) as usize * 2).max(1);
+        let mut misses = vec![0usize; num_cycles.div_ceil(bucket)];
+        for event in history.events.iter().filter(|e| e.kind == EventKind::Miss) {
+            misses[event.cycle / bucket] += 1;
+        }
+        let max = misses.iter().copied().max().unwrap_or(0).max(1);
+
+        let points: Vec<(f64, f64)> = misses
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| ((i * bucket) as f64, count as f64))
+            .collect();
+        let cursor = [(current as f64, 0.0), (current as f64, max as f64)];
+        let datasets = vec![
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(Color::Red))
+                .data(&points),
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(Color::Yellow))
+                .data(&cursor),
+        ];
+
+        let clock = |i: usize| history.clocks.get(i).copied().unwrap_or(0).to_string();
+        let last = num_cycles.saturating_sub(1);
+        let title = if bucket == 1 {
+            String::from("Misses per cycle")
+        } else {
+            format!("Misses per {bucket} cycles")
+        };
+        Chart::new(datasets)
+            .block(Block::bordered().title(Line::from(title).bold()))
+            .x_axis(
+                Axis::default()
+                    .bounds([0.0, last.max(1) as f64])
+                    .labels([clock(0), clock(last)]),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([0.0, max as f64])
+                    .labels([String::from("0"), max.to_string()]),
+            )
+            .render(area, buf);
+    }
+}
+
+impl StructureView for CacheStats {
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        Self::new(base, discovery.snapshots)
+    }
+
+    fn name(&self) -> String {
+        String::from("Cache Stats")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('M') => self.metric = (self.metric + 1) % EventKind::ALL.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let history = self
+            .history
+            .get_or_init(|| CacheHistory::extract(&self.base, snapshots));
+        let current = snapshots.cycle();
+
+        let hits = history.count(EventKind::Hit) + history.unplaced_hits;
+        let misses = history.count(EventKind::Miss);
+        let mut summary = format!(
+            "{hits} hits, {misses} misses (fills), {} evictions, {} writebacks",
+            history.count(EventKind::Eviction),
+            history.count(EventKind::Writeback),
+        );
+        if hits + misses > 0 {
+            let rate = 100.0 * hits as f64 / (hits + misses) as f64;
+            summary += &format!(", {rate:.1}% hit rate");
+        }
+        if history.unplaced_hits > 0 {
+            summary += &format!(" ({} hits not matched to a line)", history.unplaced_hits);
+        }
+
+        let title = Line::from(format!(
+            "Cache Stats ({} sets x {} ways)",
+            history.num_sets, history.num_ways
+        ))
+        .bold()
+        .centered();
+        let hint = Line::from(format!(
+            " <M> heatmap of {} ",
+            EventKind::ALL[self.metric].name()
+        ))
+        .centered();
+        let block = Block::bordered().title(title).title_bottom(hint);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [summary_area, heatmap_area, timeline_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Fill(1),
+            Constraint::Length(TIMELINE_HEIGHT),
+        ])
+        .areas(inner);
+        Paragraph::new(summary).render(summary_area, buf);
+        self.render_heatmap(history, current, heatmap_area, buf);
+        self.render_timeline(history, current, timeline_area, buf);
+    }
+}
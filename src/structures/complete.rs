@@ -9,6 +9,8 @@ use ratatui::{
 use crate::utils::{Columns, DisplayType, LEFT_BORDER_SET};
 use crate::{snapshots::Snapshots, utils::Column};
 
+use super::view::impl_structure_view;
+
 #[derive(Clone)]
 pub struct Complete {
     base: String,
@@ -119,3 +121,5 @@ impl StatefulWidget for Complete {
         Widget::render(cdb_etb_table, top_right_area, buf);
     }
 }
+
+impl_structure_view!(Complete, "Complete");
@@ -11,6 +11,8 @@ use crate::{
     utils::{parse_mem_command, parse_mem_size, Columns, COLORS},
 };
 
+use super::view::impl_structure_view;
+
 #[derive(Clone, Debug)]
 pub struct DCache {
     base: String,
@@ -56,12 +58,11 @@ impl DCache {
         })
     }
 
-    fn get_outputs(&self, snapshots: &Snapshots) -> Line {
-        let valid = snapshots.get_var(&format!("{}.valid", self.base)).unwrap();
-        let ready = snapshots.get_var(&format!("{}.ready", self.base)).unwrap();
+    fn get_outputs(&self, snapshots: &Snapshots) -> Line<'_> {
+        let valid = snapshots.get_var_or_missing(&format!("{}.valid", self.base));
+        let ready = snapshots.get_var_or_missing(&format!("{}.ready", self.base));
         let data = snapshots
-            .get_var(&format!("{}.data", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.data", self.base))
             .as_hex();
 
         let mut parts = vec!["Outputs:".blue().bold()];
@@ -89,27 +90,22 @@ impl DCache {
         Line::from(parts)
     }
 
-    fn get_memdp_ports(&self, snapshots: &Snapshots) -> Line {
+    fn get_memdp_ports(&self, snapshots: &Snapshots) -> Line<'_> {
         let read_enable = snapshots
-            .get_var(&format!("{}.read_enable", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.read_enable", self.base))
             .is_high();
         let read_index = snapshots
-            .get_var(&format!("{}.read_index", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.read_index", self.base))
             .as_decimal();
 
         let write_enable = snapshots
-            .get_var(&format!("{}.write_enable", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.write_enable", self.base))
             .is_high();
         let write_index = snapshots
-            .get_var(&format!("{}.write_index", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.write_index", self.base))
             .as_decimal();
         let write_data = snapshots
-            .get_var(&format!("{}.write_data.dbbl_level", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.write_data.dbbl_level", self.base))
             .as_hex();
 
         let mut parts = vec!["memDP ports: ".blue().bold()];
@@ -139,22 +135,24 @@ impl DCache {
         Line::from(parts)
     }
 
-    fn get_incoming_command(&self, snapshots: &Snapshots) -> Line {
+    fn get_incoming_command(&self, snapshots: &Snapshots) -> Line<'_> {
         let command_key = format!("{}.query_command", self.base);
-        let command = snapshots.get_var(&command_key).unwrap();
+        let command = snapshots.get_var_or_missing(&command_key);
 
         let command_string = parse_mem_command(command);
 
         let addr_key = format!("{}.query_addr", self.base);
-        let addr = snapshots.get_var(&addr_key).unwrap().as_hex();
+        let addr = snapshots
+            .get_var_or_missing(&addr_key)
+            .as_addr(&snapshots.context);
 
         let size_key = format!("{}.query_size", self.base);
-        let size = snapshots.get_var(&size_key).unwrap();
+        let size = snapshots.get_var_or_missing(&size_key);
 
         let size_string = parse_mem_size(size);
 
         let data_key = format!("{}.query_data.dbbl_level", self.base);
-        let data = snapshots.get_var(&data_key).unwrap().as_hex();
+        let data = snapshots.get_var_or_missing(&data_key).as_hex();
 
         let mut parts = vec![
             "Incoming Command: ".blue().bold(),
@@ -176,17 +174,19 @@ impl DCache {
         Line::from(parts)
     }
 
-    fn get_mem_command(&self, snapshots: &Snapshots) -> Line {
+    fn get_mem_command(&self, snapshots: &Snapshots) -> Line<'_> {
         let command_key = format!("{}.mem_command", self.base);
-        let command = snapshots.get_var(&command_key).unwrap();
+        let command = snapshots.get_var_or_missing(&command_key);
 
         let command_string = parse_mem_command(command);
 
         let addr_key = format!("{}.mem_addr", self.base);
-        let addr = snapshots.get_var(&addr_key).unwrap().as_hex();
+        let addr = snapshots
+            .get_var_or_missing(&addr_key)
+            .as_addr(&snapshots.context);
 
         let data_key = format!("{}.mem_command_data.dbbl_level", self.base);
-        let data = snapshots.get_var(&data_key).unwrap().as_hex();
+        let data = snapshots.get_var_or_missing(&data_key).as_hex();
 
         let mut parts = vec![
             "Memory Command: ".blue().bold(),
@@ -221,7 +221,7 @@ impl DCache {
         table.block(block)
     }
 
-    fn get_set_table(&self, set_num: usize, snapshots: &Snapshots) -> Table {
+    fn get_set_table(&self, set_num: usize, snapshots: &Snapshots) -> Table<'_> {
         let columns = Columns::new(DCACHE_META_HEADERS.to_vec());
         let widths = columns.get_widths();
 
@@ -231,25 +231,24 @@ impl DCache {
             let row_base = format!("{}.metadata[{set_num}][{set_index}]", self.base);
             let index = set_num * self.num_ways + set_index;
             let is_valid = snapshots
-                .get_var(&format!("{row_base}.valid"))
-                .unwrap()
+                .get_var_or_missing(&format!("{row_base}.valid"))
                 .is_high();
 
             for col in DCACHE_META_HEADERS {
                 let string = if let Some(key) = col.key {
                     let full_key = format!("{row_base}.{key}");
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
 
-                    value.format(&col.display_type)
+                    value.format(&col.display_type, &snapshots.context)
                 } else {
                     match col.name {
                         "data" => {
                             let key = format!("{}.dcache_mem.memData[{index}]", self.base);
-                            let value = snapshots.get_var(&key).unwrap().as_hex();
+                            let value = snapshots.get_var_or_missing(&key).as_hex();
                             value
                         }
                         "addr" => {
-                            let tag = snapshots.get_var(&format!("{row_base}.tag")).unwrap();
+                            let tag = snapshots.get_var_or_missing(&format!("{row_base}.tag"));
                             let set_num = VerilogValue::from_usize(
                                 set_num,
                                 self.num_sets.ilog2().try_into().unwrap(),
@@ -258,7 +257,7 @@ impl DCache {
 
                             let addr = tag + &(&set_num + &block_offset);
 
-                            addr.as_hex()
+                            addr.as_addr(&snapshots.context)
                         }
                         "set_num" => set_num.to_string(),
                         "set_idx" => set_index.to_string(),
@@ -356,3 +355,5 @@ impl StatefulWidget for DCache {
         Widget::render(mshr_table, right, buf);
     }
 }
+
+impl_structure_view!(DCache, "Data Cache");
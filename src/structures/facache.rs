@@ -8,10 +8,13 @@ use ratatui::{
 };
 
 use crate::{
+    headers::MSHR_HEADERS,
     snapshots::Snapshots,
-    utils::{parse_mem_command, parse_mem_size},
+    utils::{parse_mem_command, parse_mem_size, Columns},
 };
 
+use super::view::impl_structure_view;
+
 // true if we can use the raw name as the key to index
 const HEADERS: [(&str, bool); 5] = [
     ("#", false),
@@ -21,15 +24,6 @@ const HEADERS: [(&str, bool); 5] = [
     ("data", false),
 ];
 
-const MSHR_HEADERS: [(&str, bool); 6] = [
-    ("#", false),
-    ("mem_tag", true),
-    ("addr", false),
-    ("size", true),
-    ("is_store", true),
-    ("store_data", true),
-];
-
 #[derive(Clone, Debug)]
 pub struct FaCache {
     base: String,
@@ -39,7 +33,7 @@ pub struct FaCache {
 
 impl FaCache {
     pub fn new(base: &str, snapshots: &Snapshots) -> Option<Self> {
-        // check that this is a dcache
+        // check that this is a fully associative cache
         snapshots.get_var(&format!("{base}.dbg_this_is_facache"))?;
 
         let mut size = 0;
@@ -65,12 +59,11 @@ impl FaCache {
         })
     }
 
-    fn get_outputs(&self, snapshots: &Snapshots) -> Line {
-        let valid = snapshots.get_var(&format!("{}.valid", self.base)).unwrap();
-        let ready = snapshots.get_var(&format!("{}.ready", self.base)).unwrap();
+    fn get_outputs(&self, snapshots: &Snapshots) -> Line<'_> {
+        let valid = snapshots.get_var_or_missing(&format!("{}.valid", self.base));
+        let ready = snapshots.get_var_or_missing(&format!("{}.ready", self.base));
         let data = snapshots
-            .get_var(&format!("{}.data", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.data", self.base))
             .as_hex();
 
         let mut parts = vec!["Outputs:".blue().bold()];
@@ -98,27 +91,22 @@ impl FaCache {
         Line::from(parts)
     }
 
-    fn get_memdp_ports(&self, snapshots: &Snapshots) -> Line {
+    fn get_memdp_ports(&self, snapshots: &Snapshots) -> Line<'_> {
         let read_enable = snapshots
-            .get_var(&format!("{}.read_enable", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.read_enable", self.base))
             .is_high();
         let read_index = snapshots
-            .get_var(&format!("{}.read_index", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.read_index", self.base))
             .as_decimal();
 
         let write_enable = snapshots
-            .get_var(&format!("{}.read_enable", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.write_enable", self.base))
             .is_high();
         let write_index = snapshots
-            .get_var(&format!("{}.write_index", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.write_index", self.base))
             .as_decimal();
         let write_data = snapshots
-            .get_var(&format!("{}.write_data.dbbl_level", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.write_data.dbbl_level", self.base))
             .as_hex();
 
         let mut parts = vec!["memDP ports: ".blue().bold()];
@@ -148,22 +136,24 @@ impl FaCache {
         Line::from(parts)
     }
 
-    fn get_incoming_command(&self, snapshots: &Snapshots) -> Line {
+    fn get_incoming_command(&self, snapshots: &Snapshots) -> Line<'_> {
         let command_key = format!("{}.query_command", self.base);
-        let command = snapshots.get_var(&command_key).unwrap();
+        let command = snapshots.get_var_or_missing(&command_key);
 
         let command_string = parse_mem_command(command);
 
         let addr_key = format!("{}.query_addr", self.base);
-        let addr = snapshots.get_var(&addr_key).unwrap().as_hex();
+        let addr = snapshots
+            .get_var_or_missing(&addr_key)
+            .as_addr(&snapshots.context);
 
         let size_key = format!("{}.query_size", self.base);
-        let size = snapshots.get_var(&size_key).unwrap();
+        let size = snapshots.get_var_or_missing(&size_key);
 
         let size_string = parse_mem_size(size);
 
         let data_key = format!("{}.query_data.dbbl_level", self.base);
-        let data = snapshots.get_var(&data_key).unwrap().as_hex();
+        let data = snapshots.get_var_or_missing(&data_key).as_hex();
 
         let mut parts = vec![
             "Incoming Command: ".blue().bold(),
@@ -185,20 +175,27 @@ impl FaCache {
         Line::from(parts)
     }
 
-    fn get_mem_command(&self, snapshots: &Snapshots) -> Line {
+    fn get_mem_command(&self, snapshots: &Snapshots) -> Line<'_> {
         let command_key = format!("{}.mem_command", self.base);
-        let command = snapshots.get_var(&command_key).unwrap();
+        let command = snapshots.get_var_or_missing(&command_key);
 
         let command_string = parse_mem_command(command);
 
         let addr_key = format!("{}.mem_addr", self.base);
-        let addr = snapshots.get_var(&addr_key).unwrap().as_hex();
+        let addr = snapshots
+            .get_var_or_missing(&addr_key)
+            .as_addr(&snapshots.context);
+
+        let data_key = format!("{}.mem_command_data.dbbl_level", self.base);
+        let data = snapshots.get_var_or_missing(&data_key).as_hex();
 
         let mut parts = vec![
             "Memory Command: ".blue().bold(),
             command_string.magenta(),
             " at ".into(),
             addr.magenta(),
+            " with data ".into(),
+            data.magenta(),
         ];
 
         if command.is_low() || command.is_unknown() {
@@ -210,69 +207,44 @@ impl FaCache {
         Line::from(parts)
     }
 
-    fn get_mshr_table(&self, snapshots: &Snapshots) -> Table {
-        let mut widths: Vec<u16> = MSHR_HEADERS.iter().map(|(x, _)| x.len() as u16).collect();
-        let header = Row::new(MSHR_HEADERS.map(|(x, _)| x)).bold().on_blue();
-
-        let mut rows = Vec::new();
-
-        for i in 0..self.num_mshrs {
-            let mut row_cells: Vec<Cell> = vec![];
-            let row_base = format!("{}.waiting_commands[{i}]", self.base);
-            let is_valid = snapshots
-                .get_var(&format!("{row_base}.valid"))
-                .unwrap()
-                .is_high();
+    fn get_mshr_table<'a>(&self, snapshots: &'a Snapshots) -> Table<'a> {
+        let columns = Columns::new(MSHR_HEADERS.to_vec());
 
-            for (j, (name, is_key)) in MSHR_HEADERS.iter().enumerate() {
-                let string = if *is_key {
-                    let full_key = format!("{row_base}.{name}");
-                    let value = snapshots.get_var(&full_key).unwrap();
+        let bases = (0..self.num_mshrs)
+            .map(|i| format!("{}.waiting_commands[{i}]", self.base))
+            .collect();
+        columns.create_table(bases, snapshots)
+    }
 
-                    // string that gets displayed in the cell section
-                    match *name {
-                        "mem_tag" => value.as_decimal(),
-                        "store_data" => value.as_hex(),
-                        "size" => parse_mem_size(value).to_string(),
-                        _ => format!("{}", value),
-                    }
-                } else if *name == "#" {
-                    i.to_string()
-                } else if *name == "addr" {
-                    let tag = snapshots.get_var(&format!("{row_base}.addr.tag")).unwrap();
-                    // let block_num = snapshots
-                    // .get_var(&format!("{row_base}.addr.block_num"))
-                    // .unwrap();
-                    let offset = snapshots
-                        .get_var(&format!("{row_base}.addr.block_offset"))
-                        .unwrap();
-
-                    let addr = tag + offset;
-
-                    addr.as_hex()
-                } else {
-                    unreachable!()
-                };
+    fn get_hit(&self, snapshots: &Snapshots) -> Line<'_> {
+        let command = snapshots.get_var_or_missing(&format!("{}.query_command", self.base));
+        let hit = snapshots.get_var_or_missing(&format!("{}.hit", self.base));
+        let hit_index = snapshots
+            .get_var_or_missing(&format!("{}.hit_index", self.base))
+            .as_decimal();
 
-                let width = string.len();
-                widths[j] = max(widths[j], width as u16);
-                row_cells.push(Cell::new(string));
-            }
+        let mut parts = vec!["Lookup: ".blue().bold()];
 
-            let mut row = Row::new(row_cells);
+        if hit.is_high() {
+            parts.push("hit".green().bold());
+            parts.push(" at index ".into());
+            parts.push(hit_index.magenta());
+        } else if hit.is_unknown() {
+            parts.push(format!("{hit}").magenta());
+        } else {
+            parts.push("miss".red().bold());
+        }
 
-            // formatting, colors
-            if !is_valid {
-                row = row.dim();
+        if command.is_low() || command.is_unknown() {
+            #[allow(clippy::needless_range_loop)]
+            for i in 1..parts.len() {
+                parts[i] = parts[i].clone().dim();
             }
-
-            rows.push(row)
         }
-
-        Table::new(rows, widths).header(header)
+        Line::from(parts)
     }
 
-    fn get_table(&self, snapshots: &Snapshots) -> Table {
+    fn get_table(&self, snapshots: &Snapshots) -> Table<'_> {
         let mut widths: Vec<u16> = HEADERS.iter().map(|(x, _)| x.len() as u16).collect();
         let header = Row::new(HEADERS.map(|(x, _)| x)).bold().on_blue();
 
@@ -282,20 +254,19 @@ impl FaCache {
             let mut row_cells: Vec<Cell> = vec![];
             let row_base = format!("{}.metadata[{i}]", self.base);
             let is_valid = snapshots
-                .get_var(&format!("{row_base}.valid"))
-                .unwrap()
+                .get_var_or_missing(&format!("{row_base}.valid"))
                 .is_high();
 
             for (j, (name, is_key)) in HEADERS.iter().enumerate() {
                 let string = if *is_key {
                     let full_key = format!("{row_base}.{name}");
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
 
                     // string that gets displayed in the cell section
                     format!("{}", value)
                 } else if *name == "data" {
                     let key = format!("{}.dcache_mem.memData[{i}]", self.base);
-                    let value = snapshots.get_var(&key).unwrap().as_hex();
+                    let value = snapshots.get_var_or_missing(&key).as_hex();
                     value
                 } else if *name == "#" {
                     i.to_string()
@@ -338,6 +309,7 @@ impl StatefulWidget for FaCache {
 
         let lines = vec![
             self.get_incoming_command(snapshots),
+            self.get_hit(snapshots),
             self.get_mem_command(snapshots),
             self.get_memdp_ports(snapshots),
             self.get_outputs(snapshots),
@@ -361,3 +333,5 @@ impl StatefulWidget for FaCache {
         Widget::render(mshr_table, right, buf);
     }
 }
+
+impl_structure_view!(FaCache, "FA Cache");
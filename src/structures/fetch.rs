@@ -7,6 +7,8 @@ use ratatui::{
 
 use crate::{snapshots::Snapshots, utils::parse_inst};
 
+use super::view::impl_structure_view;
+
 #[derive(Clone, Debug)]
 pub struct Fetch {
     base: String,
@@ -31,15 +33,13 @@ impl Fetch {
         })
     }
 
-    fn get_pc(&self, snapshots: &Snapshots) -> Line {
+    fn get_pc(&self, snapshots: &Snapshots) -> Line<'_> {
         let pc = snapshots
-            .get_var(&format!("{}.PC", self.base))
-            .unwrap()
-            .as_hex();
+            .get_var_or_missing(&format!("{}.PC", self.base))
+            .as_addr(&snapshots.context);
         let next_pc = snapshots
-            .get_var(&format!("{}.PC_n", self.base))
-            .unwrap()
-            .as_hex();
+            .get_var_or_missing(&format!("{}.PC_n", self.base))
+            .as_addr(&snapshots.context);
 
         let parts = vec![
             "PC: ".blue().bold(),
@@ -51,7 +51,7 @@ impl Fetch {
         Line::from(parts)
     }
 
-    fn get_inst_table(&self, snapshots: &Snapshots) -> Table {
+    fn get_inst_table(&self, snapshots: &Snapshots) -> Table<'_> {
         let header = Row::new(vec!["#", "parsed", "raw"]).bold().on_blue();
         let widths = vec![2, 25, 10];
 
@@ -70,8 +70,7 @@ impl Fetch {
             cells.push(parse_inst(&row_base, snapshots));
             cells.push(
                 snapshots
-                    .get_var(&format!("{row_base}.inst"))
-                    .unwrap()
+                    .get_var_or_missing(&format!("{row_base}.inst"))
                     .as_hex(),
             );
 
@@ -118,3 +117,5 @@ impl StatefulWidget for Fetch {
         Widget::render(inst_table, areas[1], buf);
     }
 }
+
+impl_structure_view!(Fetch, "Fetch");
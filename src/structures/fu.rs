@@ -11,6 +11,8 @@ use crate::{
     utils::{Columns, TOP_BORDER_SET},
 };
 
+use super::view::impl_structure_view;
+
 #[derive(Clone, Debug)]
 pub struct FU {
     base: String,
@@ -190,3 +192,5 @@ impl StatefulWidget for FU {
         Widget::render(self.get_stalling_branch_table(snapshots), areas[6], buf);
     }
 }
+
+impl_structure_view!(FU, "Functional Units");
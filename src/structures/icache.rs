@@ -13,6 +13,8 @@ use crate::{
     utils::{parse_mem_command, parse_mem_size},
 };
 
+use super::view::impl_structure_view;
+
 // true if we can use the raw name as the key to index
 const HEADERS: [(&str, bool); 4] = [
     ("#", false),
@@ -65,12 +67,11 @@ impl ICache {
         })
     }
 
-    fn get_outputs(&self, snapshots: &Snapshots) -> Line {
-        let valid = snapshots.get_var(&format!("{}.valid", self.base)).unwrap();
-        let ready = snapshots.get_var(&format!("{}.ready", self.base)).unwrap();
+    fn get_outputs(&self, snapshots: &Snapshots) -> Line<'_> {
+        let valid = snapshots.get_var_or_missing(&format!("{}.valid", self.base));
+        let ready = snapshots.get_var_or_missing(&format!("{}.ready", self.base));
         let data = snapshots
-            .get_var(&format!("{}.data.dbbl_level", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.data.dbbl_level", self.base))
             .as_hex();
 
         let mut parts = vec!["Outputs:".blue().bold()];
@@ -98,27 +99,22 @@ impl ICache {
         Line::from(parts)
     }
 
-    fn get_memdp_ports(&self, snapshots: &Snapshots) -> Line {
+    fn get_memdp_ports(&self, snapshots: &Snapshots) -> Line<'_> {
         let read_enable = snapshots
-            .get_var(&format!("{}.read_enable", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.read_enable", self.base))
             .is_high();
         let read_index = snapshots
-            .get_var(&format!("{}.read_index", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.read_index", self.base))
             .as_decimal();
 
         let write_enable = snapshots
-            .get_var(&format!("{}.read_enable", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.read_enable", self.base))
             .is_high();
         let write_index = snapshots
-            .get_var(&format!("{}.write_index", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.write_index", self.base))
             .as_decimal();
         let write_data = snapshots
-            .get_var(&format!("{}.write_data.dbbl_level", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.write_data.dbbl_level", self.base))
             .as_hex();
 
         let mut parts = vec!["memDP ports: ".blue().bold()];
@@ -148,17 +144,19 @@ impl ICache {
         Line::from(parts)
     }
 
-    fn get_incoming_command(&self, snapshots: &Snapshots) -> Line {
+    fn get_incoming_command(&self, snapshots: &Snapshots) -> Line<'_> {
         let command_key = format!("{}.query_command", self.base);
-        let command = snapshots.get_var(&command_key).unwrap();
+        let command = snapshots.get_var_or_missing(&command_key);
 
         let command_string = parse_mem_command(command);
 
         let addr_key = format!("{}.query_addr", self.base);
-        let addr = snapshots.get_var(&addr_key).unwrap().as_hex();
+        let addr = snapshots
+            .get_var_or_missing(&addr_key)
+            .as_addr(&snapshots.context);
 
         let size_key = format!("{}.query_size", self.base);
-        let size = snapshots.get_var(&size_key).unwrap();
+        let size = snapshots.get_var_or_missing(&size_key);
 
         let size_string = parse_mem_size(size);
 
@@ -180,17 +178,19 @@ impl ICache {
         Line::from(parts)
     }
 
-    fn get_mem_command(&self, snapshots: &Snapshots) -> Line {
+    fn get_mem_command(&self, snapshots: &Snapshots) -> Line<'_> {
         let command_key = format!("{}.mem_command", self.base);
-        let command = snapshots.get_var(&command_key).unwrap();
+        let command = snapshots.get_var_or_missing(&command_key);
 
         let command_string = parse_mem_command(command);
 
         let addr_key = format!("{}.mem_addr", self.base);
-        let addr = snapshots.get_var(&addr_key).unwrap().as_hex();
+        let addr = snapshots
+            .get_var_or_missing(&addr_key)
+            .as_addr(&snapshots.context);
 
         let data_key = format!("{}.mem_command_data.dbbl_level", self.base);
-        let data = snapshots.get_var(&data_key).unwrap().as_hex();
+        let data = snapshots.get_var_or_missing(&data_key).as_hex();
 
         let mut parts = vec![
             "Memory Command: ".blue().bold(),
@@ -210,7 +210,7 @@ impl ICache {
         Line::from(parts)
     }
 
-    fn get_mshr_table(&self, snapshots: &Snapshots) -> Table {
+    fn get_mshr_table(&self, snapshots: &Snapshots) -> Table<'_> {
         let mut widths: Vec<u16> = MSHR_HEADERS.iter().map(|(x, _)| x.len() as u16).collect();
         let header = Row::new(MSHR_HEADERS.map(|(x, _)| x)).bold().on_blue();
 
@@ -220,15 +220,14 @@ impl ICache {
             let mut row_cells: Vec<Cell> = vec![];
             let row_base = format!("{}.waiting_commands[{i}]", self.base);
             let is_valid = snapshots
-                .get_var(&format!("{row_base}.valid"))
-                .unwrap()
+                .get_var_or_missing(&format!("{row_base}.valid"))
                 .is_high();
 
             for (j, (name, is_key)) in MSHR_HEADERS.iter().enumerate() {
                 let string = if *is_key {
                     let full_key = format!("{row_base}.{name}");
                     trace_dbg!(&full_key);
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
 
                     // string that gets displayed in the cell section
                     match *name {
@@ -240,17 +239,15 @@ impl ICache {
                 } else if *name == "#" {
                     i.to_string()
                 } else if *name == "addr" {
-                    let tag = snapshots.get_var(&format!("{row_base}.addr.tag")).unwrap();
-                    let block_num = snapshots
-                        .get_var(&format!("{row_base}.addr.block_num"))
-                        .unwrap();
-                    let offset = snapshots
-                        .get_var(&format!("{row_base}.addr.block_offset"))
-                        .unwrap();
+                    let tag = snapshots.get_var_or_missing(&format!("{row_base}.addr.tag"));
+                    let block_num =
+                        snapshots.get_var_or_missing(&format!("{row_base}.addr.block_num"));
+                    let offset =
+                        snapshots.get_var_or_missing(&format!("{row_base}.addr.block_offset"));
 
                     let addr = &(tag + block_num) + offset;
 
-                    addr.as_hex()
+                    addr.as_addr(&snapshots.context)
                 } else {
                     unreachable!()
                 };
@@ -273,7 +270,7 @@ impl ICache {
         Table::new(rows, widths).header(header)
     }
 
-    fn get_table(&self, snapshots: &Snapshots) -> Table {
+    fn get_table(&self, snapshots: &Snapshots) -> Table<'_> {
         let mut widths: Vec<u16> = HEADERS.iter().map(|(x, _)| x.len() as u16).collect();
         let header = Row::new(HEADERS.map(|(x, _)| x)).bold().on_blue();
 
@@ -283,15 +280,14 @@ impl ICache {
             let mut row_cells: Vec<Cell> = vec![];
             let row_base = format!("{}.metadata[{i}]", self.base);
             let is_valid = snapshots
-                .get_var(&format!("{row_base}.valid"))
-                .unwrap()
+                .get_var_or_missing(&format!("{row_base}.valid"))
                 .is_high();
 
             for (j, (name, is_key)) in HEADERS.iter().enumerate() {
                 let string = if *is_key {
                     let full_key = format!("{row_base}.{name}");
                     trace_dbg!(&full_key);
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
 
                     if *name == "tag" {
                         value.as_hex()
@@ -302,17 +298,17 @@ impl ICache {
                     match *name {
                         "data" => {
                             let key = format!("{}.dcache_mem.memData[{i}]", self.base);
-                            let value = snapshots.get_var(&key).unwrap().as_hex();
+                            let value = snapshots.get_var_or_missing(&key).as_hex();
                             value
                         }
                         "addr" => {
-                            let tag = snapshots.get_var(&format!("{row_base}.tag")).unwrap();
+                            let tag = snapshots.get_var_or_missing(&format!("{row_base}.tag"));
                             let block_num = VerilogValue::from_usize(i, self.size.ilog2() as usize);
                             let block_offset = VerilogValue::from_usize(0, 3);
 
                             let addr = tag + &(&block_num + &block_offset);
 
-                            addr.as_hex()
+                            addr.as_addr(&snapshots.context)
                         }
                         "#" => i.to_string(),
                         _ => unreachable!(),
@@ -377,3 +373,5 @@ impl StatefulWidget for ICache {
         Widget::render(mshr_table, right, buf);
     }
 }
+
+impl_structure_view!(ICache, "Instruction Cache");
@@ -0,0 +1,160 @@
+use std::cell::{Cell, OnceCell};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph, Row, StatefulWidget, Table, TableState, Widget},
+};
+
+use crate::{
+    invariants::{Check, InvariantLog},
+    snapshots::Snapshots,
+};
+
+use super::{view::select_row, Discovery, StructureView};
+
+const HEADERS: [&str; 4] = ["from clock", "cycles", "check", "detail"];
+const WIDTHS: [Constraint; 4] = [
+    Constraint::Length(10),
+    Constraint::Length(6),
+    Constraint::Length(16),
+    Constraint::Fill(1),
+];
+
+/// Cycles where the core's structures got into states that should be impossible.
+pub struct Invariants {
+    base: String,
+    /// collected the first time it's shown, since it has to go through the whole trace
+    log: OnceCell<InvariantLog>,
+    /// row selected with <J/K>
+    selected: Option<usize>,
+    /// cycle the view was last shown at, to jump to violations relative to
+    cursor: Cell<usize>,
+    jump: Option<usize>,
+}
+
+impl Invariants {
+    pub fn new(base: &str) -> Self {
+        Self {
+            base: base.to_owned(),
+            log: OnceCell::new(),
+            selected: None,
+            cursor: Cell::new(0),
+            jump: None,
+        }
+    }
+}
+
+impl StructureView for Invariants {
+    /// Checks the whole core, as long as it has any of the structures that are checked.
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        discovery
+            .has(&["rob", "rs", "map_table"])
+            .then(|| Self::new(base))
+    }
+
+    fn name(&self) -> String {
+        String::from("Invariants")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        let Some(log) = self.log.get() else {
+            return false;
+        };
+        if select_row(&mut self.selected, log.violations.len(), key) {
+            return true;
+        }
+        match key.code {
+            KeyCode::Enter => {
+                self.jump = self
+                    .selected
+                    .and_then(|i| log.violations.get(i))
+                    .map(|violation| violation.cycles.start);
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                let forward = key.code == KeyCode::Char('n');
+                self.jump = log.next_violation(self.cursor.get(), forward);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn take_jump(&mut self) -> Option<usize> {
+        self.jump.take()
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let log = self
+            .log
+            .get_or_init(|| InvariantLog::collect(&self.base, snapshots));
+        let current = snapshots.cycle();
+        self.cursor.set(current);
+
+        let summary: Vec<Line> = Check::ALL
+            .iter()
+            .zip(log.checked)
+            .map(|(&check, checked)| {
+                let count = log.count(check);
+                let first = log.violations.iter().find(|v| v.check == check);
+                let status = match first {
+                    _ if !checked => "not checked, missing structures".dim(),
+                    None => "ok".green(),
+                    Some(first) => {
+                        format!("{count} violations, first at clock {}", first.clock).red()
+                    }
+                };
+                Line::from(vec![format!("{:<18}", check.name()).into(), status])
+            })
+            .collect();
+
+        let rows = log.violations.iter().map(|violation| {
+            let row = Row::new(vec![
+                violation.clock.to_string(),
+                violation.cycles.len().to_string(),
+                violation.check.name().to_owned(),
+                violation.detail.clone(),
+            ]);
+            if violation.cycles.contains(&current) {
+                row.yellow()
+            } else {
+                row
+            }
+        });
+
+        let title = Line::from("Invariants").bold().centered();
+        let hint = Line::from(
+            " <J/K> select, <Enter> jump to it, <n/N> next/previous violation from the cursor ",
+        )
+        .centered();
+        let block = Block::bordered().title(title).title_bottom(hint);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [summary_area, table_area] = Layout::vertical([
+            Constraint::Length(summary.len() as u16 + 1),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+        Paragraph::new(summary).render(summary_area, buf);
+
+        if log.violations.is_empty() {
+            Paragraph::new("No violations in the trace")
+                .green()
+                .render(table_area, buf);
+            return;
+        }
+        let table = Table::new(rows, WIDTHS)
+            .header(Row::new(HEADERS).bold().on_blue())
+            .row_highlight_style(Style::new().reversed());
+        let mut state = TableState::new().with_selected(self.selected);
+        StatefulWidget::render(table, table_area, buf, &mut state);
+    }
+}
@@ -0,0 +1,144 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Row, Table, Widget},
+};
+
+use crate::{lifetime::Pipeline, program::MemoryImage, snapshots::Snapshots, utils::Scroll};
+
+use super::{Discovery, StructureView};
+
+const HEADERS: [&str; 4] = ["addr", "raw", "inst", "where"];
+const WIDTHS: [Constraint; 4] = [
+    Constraint::Length(8),
+    Constraint::Length(8),
+    Constraint::Fill(2),
+    Constraint::Fill(1),
+];
+
+/// Disassembly of the whole program, scrolled to what the core is working on.
+pub struct ProgramListing {
+    base: String,
+    program: MemoryImage,
+    /// address of every row
+    addrs: Vec<usize>,
+    /// `None` if there's no ROB to show in-flight instructions from
+    pipeline: Option<Pipeline>,
+    /// rows scrolled away from the ROB head with <J/K>
+    scroll: Scroll,
+}
+
+impl ProgramListing {
+    pub fn new(base: &str, program: &MemoryImage, snapshots: &Snapshots) -> Option<Self> {
+        let addrs = program.inst_addrs(&snapshots.context.isa);
+        if addrs.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            base: base.to_owned(),
+            program: program.clone(),
+            addrs,
+            pipeline: Pipeline::new(base, snapshots),
+            scroll: Scroll::default(),
+        })
+    }
+}
+
+impl StructureView for ProgramListing {
+    /// The listing comes from the program file rather than the trace, so it needs one.
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        Self::new(base, discovery.program?, discovery.snapshots)
+    }
+
+    fn name(&self) -> String {
+        String::from("Program")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        self.scroll.on_key_event(key)
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let state = self
+            .pipeline
+            .as_ref()
+            .map(|pipeline| pipeline.sample(snapshots))
+            .unwrap_or_default();
+        let head_pc = state
+            .rob
+            .get(state.rob_head)
+            .copied()
+            .flatten()
+            .map(|inst| inst.pc);
+
+        // what's at each PC, oldest first
+        let locations = |pc: usize| -> Vec<String> {
+            let mut locations = Vec::new();
+            for offset in 0..state.rob_count {
+                let rob_num = (state.rob_head + offset) % state.rob.len();
+                if state.rob[rob_num].is_some_and(|inst| inst.pc == pc) {
+                    locations.push(format!("ROB#{rob_num}"));
+                }
+            }
+            if state.fetch.contains(&pc) {
+                locations.push(String::from("fetch"));
+            }
+            locations
+        };
+
+        // keep the oldest instruction in flight (or what's being fetched) a few rows down
+        let num_rows = area.height.saturating_sub(3) as usize;
+        let anchor = head_pc
+            .or(state.fetch.first().copied())
+            .and_then(|pc| self.addrs.iter().position(|&addr| addr >= pc))
+            .unwrap_or(0);
+        let synced = anchor.saturating_sub(num_rows / 4);
+        let first = self.scroll.first(synced, self.addrs.len(), num_rows);
+
+        let rows = self.addrs.iter().skip(first).take(num_rows).map(|&addr| {
+            let len = snapshots.context.isa.inst_len(self.program.read(addr, 4));
+            let bits = self.program.read(addr, len);
+            let locations = locations(addr);
+            let row = Row::new(vec![
+                format!("{addr:x}"),
+                format!("{bits:0width$x}", width = len * 2),
+                snapshots.context.disasm(bits),
+                locations.join(" "),
+            ]);
+            if head_pc == Some(addr) {
+                row.on_green()
+            } else if locations.iter().any(|l| l.starts_with("ROB")) {
+                row.yellow()
+            } else if !locations.is_empty() {
+                row.cyan()
+            } else {
+                row
+            }
+        });
+
+        let title = Line::from("Program").bold().centered();
+        let mut block = Block::bordered().title(title);
+        if self.pipeline.is_some() {
+            let legend = Line::from(vec![
+                " ROB head ".on_green(),
+                " ".into(),
+                "in flight".yellow(),
+                " ".into(),
+                "fetch ".cyan(),
+            ]);
+            block = block.title_bottom(legend.centered());
+        }
+        Table::new(rows, WIDTHS)
+            .header(Row::new(HEADERS).bold().on_blue())
+            .block(block)
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,186 @@
+use std::{cell::OnceCell, rc::Rc};
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Cell, Row, Table, Widget},
+};
+
+use crate::{
+    memory::{MemoryHistory, WriteSource},
+    program::MemoryImage,
+    retire::{RetireLog, Store},
+    snapshots::Snapshots,
+    utils::Scroll,
+};
+
+use super::{Discovery, StructureView};
+
+/// Bytes shown per row.
+const LINE_SIZE: usize = 16;
+/// Words written less than this many cycles ago are highlighted.
+const RECENT_CYCLES: usize = 16;
+
+const HEADERS: [&str; 6] = ["addr", "+0", "+4", "+8", "+c", "symbols"];
+const WIDTHS: [Constraint; 6] = [
+    Constraint::Length(8),
+    Constraint::Length(8),
+    Constraint::Length(8),
+    Constraint::Length(8),
+    Constraint::Length(8),
+    Constraint::Fill(1),
+];
+
+/// Hex dump of main memory as of the cursor, rebuilt from the program image and every write seen
+/// since reset.
+pub struct MainMemory {
+    base: String,
+    initial: MemoryImage,
+    /// extracted the first time it's shown, since it has to go through the whole trace
+    history: OnceCell<MemoryHistory>,
+    /// what the first core retired, for the stores it committed
+    retire_log: Rc<OnceCell<Option<RetireLog>>>,
+    /// start of every row, only covering memory that's loaded or written at some point
+    lines: OnceCell<Vec<usize>>,
+    /// rows scrolled away from the last write with <J/K>
+    scroll: Scroll,
+}
+
+impl MainMemory {
+    /// `base` is the testbench, where the memory bus is. Without a program there's nothing to show
+    /// until something is written, so that needs a bus or a store queue.
+    pub fn new(
+        base: &str,
+        program: Option<&MemoryImage>,
+        retire_log: Rc<OnceCell<Option<RetireLog>>>,
+        snapshots: &Snapshots,
+    ) -> Option<Self> {
+        let has_bus = snapshots
+            .get_var(&format!("{base}.proc2mem_command"))
+            .is_some();
+        if !has_bus && program.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            base: base.to_owned(),
+            initial: program.cloned().unwrap_or_default(),
+            history: OnceCell::new(),
+            retire_log,
+            lines: OnceCell::new(),
+            scroll: Scroll::default(),
+        })
+    }
+}
+
+impl StructureView for MainMemory {
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        Self::new(
+            base,
+            discovery.program,
+            discovery.retire_log.clone(),
+            discovery.snapshots,
+        )
+    }
+
+    fn name(&self) -> String {
+        String::from("Main Memory")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        self.scroll.on_key_event(key)
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let history = self.history.get_or_init(|| {
+            let log = self
+                .retire_log
+                .get_or_init(|| RetireLog::extract(snapshots));
+            MemoryHistory::extract(&self.base, self.initial.clone(), log.as_ref(), snapshots)
+        });
+        let lines = self.lines.get_or_init(|| {
+            let mut lines: Vec<usize> = history.addrs().map(|addr| addr / LINE_SIZE).collect();
+            lines.sort_unstable();
+            lines.dedup();
+            lines.into_iter().map(|line| line * LINE_SIZE).collect()
+        });
+
+        let current = snapshots.cycle();
+        let last_write = history.writes[..history.num_writes(current)].last();
+
+        // keep the line last written to a few rows down
+        let num_rows = area.height.saturating_sub(3) as usize;
+        let anchor = last_write
+            .map(|write| write.store.addr - write.store.addr % LINE_SIZE)
+            .and_then(|line| lines.iter().position(|&l| l == line))
+            .unwrap_or(0);
+        let synced = anchor.saturating_sub(num_rows / 4);
+        let first = self.scroll.first(synced, lines.len(), num_rows);
+
+        let rows = lines.iter().skip(first).take(num_rows).map(|&line| {
+            let mut cells = vec![Cell::from(format!("{line:08x}"))];
+            let mut symbols = Vec::new();
+            for word in (line..line + LINE_SIZE).step_by(4) {
+                let style = match history.last_written(word, current) {
+                    Some(cycle) if cycle == current => Style::new().on_green(),
+                    Some(cycle) if current - cycle < RECENT_CYCLES => Style::new().yellow(),
+                    Some(_) => Style::new(),
+                    // still what the program was loaded with
+                    None => Style::new().dim(),
+                };
+                cells.push(
+                    Cell::from(format!("{:08x}", history.read(word, 4, current))).style(style),
+                );
+                // only name symbols that start here, not every word inside one
+                symbols.extend(
+                    snapshots
+                        .context
+                        .symbolize(word)
+                        .filter(|symbol| !symbol.contains('+')),
+                );
+            }
+            cells.push(Cell::from(symbols.join(" ")));
+            Row::new(cells)
+        });
+
+        let title = Line::from(format!(
+            "Main Memory ({}/{} writes)",
+            history.num_writes(current),
+            history.writes.len()
+        ))
+        .bold()
+        .centered();
+        let legend = Line::from(vec![
+            " this cycle ".on_green(),
+            " ".into(),
+            "recent ".yellow(),
+        ]);
+        let mut block = Block::bordered()
+            .title(title)
+            .title_bottom(legend.right_aligned());
+        if let Some(write) = last_write {
+            let source = match write.source {
+                WriteSource::Bus => "bus",
+                WriteSource::Commit => "commit",
+            };
+            let Store { addr, size, value } = write.store;
+            let last = format!(
+                " last: MEM[{addr:x}]={value:0width$x} ({source}, clock {}) ",
+                write.clock,
+                width = size * 2
+            );
+            block = block.title_bottom(Line::from(last).left_aligned());
+        }
+        Table::new(rows, WIDTHS)
+            .header(Row::new(HEADERS).bold().on_blue())
+            .block(block)
+            .render(area, buf);
+    }
+}
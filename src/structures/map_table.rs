@@ -1,3 +1,5 @@
+use std::{cell::OnceCell, cmp::max, rc::Rc};
+
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -6,31 +8,97 @@ use ratatui::{
     widgets::{Block, Cell, Row, StatefulWidget, Table, Widget},
 };
 
-use crate::snapshots::{Snapshots, VerilogValue};
+use crate::{retire::RetireLog, snapshots::Snapshots, utils::ABI_NAMES};
+
+use super::{nearest_marker, Discovery, StructureView};
 
-const HEADERS: [&str; 3] = ["arch_reg", "phys_reg", "data"];
-const WIDTHS: [u16; 3] = [8, 8, 10];
+const HEADERS: [&str; 6] = [
+    "arch_reg",
+    "abi",
+    "phys_reg",
+    "ready",
+    "speculative",
+    "committed",
+];
+
+/// Where the committed value of each register comes from.
+#[derive(Clone)]
+enum Committed {
+    /// the map table as of the last retired instruction, at this path
+    ArchTable(String),
+    /// the last value each register was written with by what the core at `core` retired
+    Retired {
+        core: String,
+        log: Rc<OnceCell<Option<RetireLog>>>,
+    },
+}
 
+/// Architectural register view: where each of x0-x31 currently lives in the physical register
+/// file, and what its value is there and as of the last retired instruction.
 #[derive(Clone)]
 pub struct MapTable {
     base: String,
+    /// path of the `map_table[]` array itself
+    entries: String,
     size: usize,
+    /// regfile the physical registers are read from
+    regfile: Option<String>,
+    /// `None` for tables that only hold speculative mappings, like branch stack checkpoints
+    committed: Option<Committed>,
 }
 
 impl MapTable {
-    pub fn new(base: &str, snapshots: &Snapshots) -> Self {
+    /// Table for the map table array at `entries`, e.g. a branch stack checkpoint.
+    pub fn new(entries: &str, snapshots: &Snapshots) -> Self {
         let mut i = 0;
-        let mut name = format!("{base}[{i}]");
+        let mut name = format!("{entries}[{i}]");
 
         while snapshots.get_scope(&name).is_some() {
             i += 1;
-            name = format!("{base}[{i}]");
+            name = format!("{entries}[{i}]");
         }
 
         Self {
-            base: base.to_owned(),
+            base: entries.to_owned(),
+            entries: entries.to_owned(),
             size: i,
+            regfile: nearest_marker(&snapshots.header, "regfile", entries),
+            committed: None,
+        }
+    }
+
+    /// Value each register was last written with by what retired before `cycle`, like an
+    /// architectural map table updated at the end of the cycle would have it. `None` for registers
+    /// that haven't been written since the trace started.
+    fn retired_values(&self, log: &RetireLog, cycle: usize) -> Vec<Option<usize>> {
+        let mut values = vec![None; self.size];
+        if let Some(x0) = values.first_mut() {
+            *x0 = Some(0);
         }
+        let retired = log.entries.partition_point(|entry| entry.cycle < cycle);
+        for entry in log.entries[..retired].iter().rev() {
+            if values.iter().all(Option::is_some) {
+                break;
+            }
+            if let Some(value) = entry.rd.and_then(|rd| values.get_mut(rd)) {
+                value.get_or_insert(entry.value);
+            }
+        }
+        values
+    }
+
+    fn get_phys_reg(&self, entries: &str, i: usize, snapshots: &Snapshots) -> (String, usize) {
+        let phys_reg = snapshots.get_var_or_missing(&format!("{entries}[{i}].value"));
+        (phys_reg.as_decimal(), phys_reg.as_usize())
+    }
+
+    fn get_data(&self, phys_reg: usize, snapshots: &Snapshots) -> String {
+        let Some(regfile) = &self.regfile else {
+            return String::from("no regfile!");
+        };
+        snapshots
+            .get_var_or_missing(&format!("{regfile}.regfile_mem.memData[{phys_reg}]"))
+            .as_hex()
     }
 }
 
@@ -38,66 +106,113 @@ impl StatefulWidget for MapTable {
     type State = Snapshots;
 
     fn render(self, area: Rect, buf: &mut Buffer, snapshots: &mut Self::State) {
-        let header = Row::new(HEADERS).bold().on_blue();
+        let num_headers = if self.committed.is_some() {
+            HEADERS.len()
+        } else {
+            HEADERS.len() - 1
+        };
+        let headers = &HEADERS[..num_headers];
+
+        let retired_values = match &self.committed {
+            Some(Committed::Retired { core, log }) => {
+                let log = log.get_or_init(|| RetireLog::extract_at(core, snapshots));
+                log.as_ref()
+                    .map(|log| self.retired_values(log, snapshots.cycle()))
+            }
+            _ => None,
+        };
+
+        let mut widths: Vec<u16> = headers.iter().map(|x| x.len() as u16).collect();
+        let header = Row::new(headers.to_vec()).bold().on_blue();
 
         let mut rows = Vec::new();
 
         for i in 0..self.size {
             let mut row_cells: Vec<Cell> = vec![];
-            let row_base = format!("{}[{i}]", self.base);
 
-            let mut ready = false;
+            let (phys_reg_string, phys_reg) = self.get_phys_reg(&self.entries, i, snapshots);
+            let ready = snapshots
+                .get_var_or_missing(&format!("{}[{i}].ready", self.entries))
+                .is_high();
 
-            for name in HEADERS.iter() {
+            for (j, name) in headers.iter().enumerate() {
                 let string: String = match *name {
-                    "arch_reg" => i.to_string(),
-                    "phys_reg" => {
-                        let reg_idx_key = format!("{row_base}.value");
-                        let reg_idx = snapshots.get_var(&reg_idx_key).unwrap();
-                        let string = reg_idx.as_decimal();
-
-                        let reg_ready_key = format!("{row_base}.ready");
-                        let reg_ready = snapshots.get_var(&reg_ready_key).unwrap();
-                        ready = reg_ready.is_high();
-
-                        if ready {
-                            string + "+"
-                        } else {
-                            string
+                    "arch_reg" => format!("x{i}"),
+                    "abi" => ABI_NAMES.get(i).copied().unwrap_or_default().to_owned(),
+                    "phys_reg" => phys_reg_string.clone(),
+                    "ready" => String::from(if ready { "+" } else { "" }),
+                    "speculative" => self.get_data(phys_reg, snapshots),
+                    "committed" => match (&self.committed, &retired_values) {
+                        (Some(Committed::ArchTable(arch_entries)), _) => {
+                            let (_, arch_phys_reg) = self.get_phys_reg(arch_entries, i, snapshots);
+                            self.get_data(arch_phys_reg, snapshots)
                         }
-                    }
-                    "data" => {
-                        let reg_idx_key = format!("{row_base}.value");
-                        let reg_idx = snapshots.get_var(&reg_idx_key).unwrap().as_usize();
-
-                        let tb_path = snapshots.get_base();
-                        // fixme: hardcoded as hell
-                        let data_key =
-                            format!("{tb_path}.o3o.regfile_module.regfile_mem.memData[{reg_idx}]");
-
-                        snapshots
-                            .get_var(&data_key)
-                            .map(VerilogValue::as_hex)
-                            .unwrap_or(String::from("couldn't get data!"))
-                    }
+                        (_, Some(values)) => {
+                            values[i].map_or(String::from("-"), |value| format!("{value:#x}"))
+                        }
+                        _ => String::from("-"),
+                    },
                     _ => {
                         unreachable!()
                     }
                 };
 
-                row_cells.push(Cell::new(string));
+                widths[j] = max(widths[j], string.len() as u16);
+                let mut cell = Cell::new(string);
+                // the value isn't there yet if the producer hasn't completed
+                if *name == "speculative" && !ready {
+                    cell = cell.dim();
+                }
+                row_cells.push(cell);
             }
 
-            let mut row = Row::new(row_cells);
-            if ready {
-                row = row.on_green();
-            }
-            rows.push(row)
+            rows.push(Row::new(row_cells))
         }
 
         let title = Line::from("Map Table").bold().centered();
         let block = Block::bordered().title(title);
-        let table = Table::new(rows, WIDTHS).header(header).block(block);
+        let table = Table::new(rows, widths).header(header).block(block);
         Widget::render(table, area, buf);
     }
 }
+
+impl StructureView for MapTable {
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        let snapshots = discovery.snapshots;
+        snapshots.get_scope(&format!("{base}.map_table[0]"))?;
+
+        let mut table = Self::new(&format!("{base}.map_table"), snapshots);
+        table.base = base.to_owned();
+
+        // without an architectural map table, committed values are rebuilt from what retired
+        let arch_entries = format!("{base}.arch_map_table");
+        table.committed = if snapshots.get_scope(&format!("{arch_entries}[0]")).is_some() {
+            Some(Committed::ArchTable(arch_entries))
+        } else {
+            let core = nearest_marker(&snapshots.header, "rob", base)
+                .unwrap_or_else(|| snapshots.get_base());
+            RetireLog::available(&core, snapshots).then(|| {
+                let log = if core == discovery.first_core() {
+                    discovery.retire_log.clone()
+                } else {
+                    Rc::default()
+                };
+                Committed::Retired { core, log }
+            })
+        };
+
+        Some(table)
+    }
+
+    fn name(&self) -> String {
+        String::from("Map Table")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        StatefulWidget::render(self.clone(), area, buf, snapshots);
+    }
+}
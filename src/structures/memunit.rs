@@ -11,6 +11,8 @@ use crate::{
     utils::{parse_mem_command, parse_mem_size, parse_mem_state, Columns, TOP_BORDER_SET},
 };
 
+use super::view::impl_structure_view;
+
 #[derive(Clone, Debug)]
 pub struct MemUnit {
     base: String,
@@ -26,17 +28,11 @@ impl MemUnit {
         })
     }
 
-    fn get_state(&self, snapshots: &Snapshots) -> Line {
-        let state = parse_mem_state(
-            snapshots
-                .get_var(&format!("{}.mem_state", self.base))
-                .unwrap(),
-        );
-        let next_state = parse_mem_state(
-            snapshots
-                .get_var(&format!("{}.next_mem_state", self.base))
-                .unwrap(),
-        );
+    fn get_state(&self, snapshots: &Snapshots) -> Line<'_> {
+        let state =
+            parse_mem_state(snapshots.get_var_or_missing(&format!("{}.mem_state", self.base)));
+        let next_state =
+            parse_mem_state(snapshots.get_var_or_missing(&format!("{}.next_mem_state", self.base)));
 
         let parts = vec![
             "State: ".blue().bold(),
@@ -48,20 +44,22 @@ impl MemUnit {
         Line::from(parts)
     }
 
-    fn get_cache_command(&self, snapshots: &Snapshots) -> Line {
+    fn get_cache_command(&self, snapshots: &Snapshots) -> Line<'_> {
         let command_key = format!("{}.cache_query_command", self.base);
-        let command = snapshots.get_var(&command_key).unwrap();
+        let command = snapshots.get_var_or_missing(&command_key);
         let command_string = parse_mem_command(command);
 
         let addr_key = format!("{}.cache_query_addr", self.base);
-        let addr = snapshots.get_var(&addr_key).unwrap().as_hex();
+        let addr = snapshots
+            .get_var_or_missing(&addr_key)
+            .as_addr(&snapshots.context);
 
         let size_key = format!("{}.cache_query_size", self.base);
-        let size = snapshots.get_var(&size_key).unwrap();
+        let size = snapshots.get_var_or_missing(&size_key);
         let size_string = parse_mem_size(size);
 
         let data_key = format!("{}.cache_query_data.dbbl_level", self.base);
-        let data = snapshots.get_var(&data_key).unwrap().as_hex();
+        let data = snapshots.get_var_or_missing(&data_key).as_hex();
 
         let mut parts = vec![
             "Cache Command: ".blue().bold(),
@@ -83,18 +81,14 @@ impl MemUnit {
         Line::from(parts)
     }
 
-    fn get_cache_response(&self, snapshots: &Snapshots) -> Line {
+    fn get_cache_response(&self, snapshots: &Snapshots) -> Line<'_> {
         let data = snapshots
-            .get_var(&format!("{}.cache_resp_data", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.cache_resp_data", self.base))
             .as_hex();
         let valid = snapshots
-            .get_var(&format!("{}.cache_resp_valid", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.cache_resp_valid", self.base))
             .is_high();
-        let ready = snapshots
-            .get_var(&format!("{}.cache_query_ready", self.base))
-            .unwrap();
+        let ready = snapshots.get_var_or_missing(&format!("{}.cache_query_ready", self.base));
 
         let parts = vec![
             "Cache Response: ".blue().bold(),
@@ -181,3 +175,5 @@ impl StatefulWidget for MemUnit {
         Widget::render(next_output_packet, areas[5], buf);
     }
 }
+
+impl_structure_view!(MemUnit, "Memory Unit");
@@ -1,250 +1,384 @@
+use std::{cell::OnceCell, cmp::min, rc::Rc};
+
+use array_table::ArrayTable;
 use branch_stack::BranchStack;
 use branches::Btb;
+use cache_stats::CacheStats;
 use complete::Complete;
 use crossterm::event::{KeyCode, KeyEvent};
 use dcache::DCache;
+use facache::FaCache;
 use fetch::Fetch;
 use fu::FU;
 use icache::ICache;
+use invariants::Invariants;
 use issue::Issue;
+use listing::ProgramListing;
+use main_memory::MainMemory;
+use map_table::MapTable;
 use memunit::MemUnit;
+use occupancy::Occupancy;
+use perf::PerfView;
+use pipeline::PipelineDiagram;
+use predictor::Predictor;
 use ratatui::buffer::Buffer;
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
-use ratatui::style::{Color, Style};
-use ratatui::widgets::{StatefulWidget, Tabs, Widget};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Paragraph, Tabs, Widget};
 use regfile::RegFile;
+use retire_log::RetireLogView;
 use rob::ROBTable;
 use rs::RSTable;
+use stalls::Stalls;
 use store_queue::StoreQueue;
-use vcd::ScopeItem;
+use transactions::Transactions;
+use vcd::{Header, Scope, ScopeItem};
 
+use crate::program::MemoryImage;
+use crate::retire::RetireLog;
 use crate::snapshots::Snapshots;
-use crate::utils::{split_horizontal, split_vertical};
-use strum::{EnumCount, IntoEnumIterator};
-use strum_macros::{Display, EnumCount as EnumCountMacro, EnumIter, FromRepr};
 
+pub use view::{select_row, Discovery, Source, StructureView, ViewKind};
+
+mod array_table;
 mod branch_stack;
 mod branches;
+mod cache_stats;
 mod complete;
 mod dcache;
+mod facache;
 mod fetch;
 mod fu;
 mod icache;
+mod invariants;
 mod issue;
+mod listing;
+mod main_memory;
 mod map_table;
 mod memunit;
+mod occupancy;
+mod perf;
+mod pipeline;
+mod predictor;
 mod regfile;
+mod retire_log;
 mod rob;
 mod rs;
+mod stalls;
 mod store_queue;
+mod transactions;
+mod view;
+
+/// Every view the debugger knows how to find. In cpu mode, tabs are in the order they first come
+/// up here, with their views in the same order.
+pub const REGISTRY: [ViewKind; 26] = [
+    ViewKind::of::<Fetch>("fetch").on_tab("Fetch"),
+    ViewKind::of::<ICache>("icache").on_tab("Fetch"),
+    ViewKind::of::<ProgramListing>("listing")
+        .from(Source::FirstCore)
+        .on_tab("Program"),
+    ViewKind::of::<RSTable>("rs").on_tab("RS/ROB"),
+    ViewKind::of::<ROBTable>("rob").on_tab("RS/ROB"),
+    ViewKind::of::<PipelineDiagram>("pipeline")
+        .from(Source::Each(&["rob"]))
+        .on_tab("Pipeline"),
+    ViewKind::of::<RetireLogView>("retire")
+        .from(Source::Cores)
+        .on_tab("Retired"),
+    ViewKind::of::<Btb>("btb").on_tab("Branch Stack"),
+    ViewKind::of::<BranchStack>("bstack").on_tab("Branch Stack"),
+    ViewKind::of::<MapTable>("map_table").on_tab("Registers"),
+    ViewKind::of::<RegFile>("regfile").on_tab("Registers"),
+    ViewKind::of::<Issue>("issue").on_tab("Issue/FUs"),
+    ViewKind::of::<FU>("fu").on_tab("Issue/FUs"),
+    ViewKind::of::<Complete>("complete").on_tab("Issue/FUs"),
+    ViewKind::of::<MemUnit>("memunit").on_tab("Issue/FUs"),
+    ViewKind::of::<DCache>("dcache").on_tab("Memory"),
+    ViewKind::of::<FaCache>("facache").on_tab("Memory"),
+    ViewKind::of::<StoreQueue>("store_queue").on_tab("Memory"),
+    ViewKind::of::<PerfView>("perf").from(Source::FirstCore),
+    ViewKind::of::<Occupancy>("occupancy").from(Source::FirstCore),
+    ViewKind::of::<Stalls>("stalls").from(Source::FirstCore),
+    ViewKind::of::<Invariants>("invariants").from(Source::FirstCore),
+    ViewKind::of::<Predictor>("predictor").from(Source::Each(&["fu"])),
+    ViewKind::of::<CacheStats>("cache_stats").from(Source::Each(CACHES)),
+    ViewKind::of::<Transactions>("transactions").from(Source::Testbench),
+    ViewKind::of::<MainMemory>("memory").from(Source::Testbench),
+];
+
+/// Markers of every kind of cache.
+const CACHES: &[&str] = &["dcache", "icache", "facache"];
 
-#[derive(Default, Clone, Copy, Display, FromRepr, EnumIter, EnumCountMacro)]
-enum SelectedTab {
-    #[strum(to_string = "Fetch")]
-    Fetch,
-    #[default]
-    #[strum(to_string = "RS/ROB")]
-    RsRob,
-    #[strum(to_string = "Branch Stack")]
-    BStack,
-    #[strum(to_string = "Issue/FUs")]
-    IssueFUs,
-    #[strum(to_string = "Memory")]
-    Memory,
+const DEFAULT_TAB: &str = "RS/ROB";
+
+struct Tab {
+    name: String,
+    /// one entry per view laid out on the tab, each listing every instance of that kind of view
+    /// as indices into `Structures::views`
+    slots: Vec<Vec<usize>>,
+    /// which instance of each slot is shown, e.g. the second core's RS and ROB
+    instance: usize,
+    /// slot that gets keys first, e.g. which table <J/K> moves the selection in
+    focus: usize,
 }
 
-impl SelectedTab {
-    /// Get the previous tab, if there is no previous tab return the current tab.
-    fn previous(self) -> Self {
-        let current_index: usize = self as usize;
-        let previous_index = if current_index == 0 {
-            SelectedTab::COUNT - 1
-        } else {
-            current_index - 1
-        };
-        Self::from_repr(previous_index).unwrap_or(self)
+impl Tab {
+    fn new(name: String, slots: Vec<Vec<usize>>) -> Self {
+        Self {
+            name,
+            slots,
+            instance: 0,
+            focus: 0,
+        }
     }
 
-    /// Get the next tab, if there is no next tab return the current tab.
-    fn next(self) -> Self {
-        let current_index = self as usize;
-        let next_index = if current_index == SelectedTab::COUNT - 1 {
-            0
-        } else {
-            current_index + 1
-        };
-        Self::from_repr(next_index).unwrap_or(self)
+    fn num_instances(&self) -> usize {
+        self.slots.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// Indices of the views currently shown, one per slot.
+    fn visible(&self) -> Vec<usize> {
+        self.slots
+            .iter()
+            .map(|slot| slot[min(self.instance, slot.len() - 1)])
+            .collect()
     }
 }
 
-#[derive(Clone)]
 pub struct Structures {
-    is_cpu: bool,
-    rs: Option<RSTable>,
-    rob: Option<ROBTable>,
-    bstack: Option<BranchStack>,
-    btb: Option<Btb>,
-    issue: Option<Issue>,
-    dcache: Option<DCache>,
-    icache: Option<ICache>,
-    fetch: Option<Fetch>,
-    regfile: Option<RegFile>,
-    store_queue: Option<StoreQueue>,
-    memunit: Option<MemUnit>,
-    fu: Option<FU>,
-    complete: Option<Complete>,
-    selected_tab: SelectedTab,
+    /// every discovered view, alongside the marker it was found by
+    views: Vec<(&'static str, Box<dyn StructureView>)>,
+    tabs: Vec<Tab>,
+    selected_tab: usize,
 }
 
 impl Structures {
     fn render_tabs(&self, area: Rect, buf: &mut Buffer) {
-        let titles = SelectedTab::iter().map(|x| x.to_string());
+        let titles = self.tabs.iter().map(|tab| {
+            if tab.num_instances() > 1 {
+                format!(
+                    "{} [{}/{}]",
+                    tab.name,
+                    tab.instance + 1,
+                    tab.num_instances()
+                )
+            } else {
+                tab.name.clone()
+            }
+        });
         let highlight_style = Style::new().bg(Color::Blue);
-        let selected_tab_index = self.selected_tab as usize;
         Tabs::new(titles)
             .highlight_style(highlight_style)
-            .select(selected_tab_index)
+            .select(self.selected_tab)
             .padding("", "")
             .divider(" ")
             .render(area, buf);
     }
 
-    pub fn on_key_event(&mut self, key: KeyEvent) {
+    /// Handles a key on the current tab. Returns the cycle to move the cursor to, if the key was
+    /// for jumping somewhere.
+    pub fn on_key_event(&mut self, key: KeyEvent) -> Option<usize> {
         match (key.modifiers, key.code) {
-            (_, KeyCode::BackTab) => self.selected_tab = self.selected_tab.previous(),
-            (_, KeyCode::Tab) => self.selected_tab = self.selected_tab.next(),
-            _ => {}
+            (_, KeyCode::BackTab) => self.previous_tab(),
+            (_, KeyCode::Tab) => self.next_tab(),
+            (_, KeyCode::Char('[')) => self.previous_instance(),
+            (_, KeyCode::Char(']')) => self.next_instance(),
+            (_, KeyCode::Char('F')) => self.next_focus(),
+            _ => {
+                let tab = self.tabs.get(self.selected_tab)?;
+                let mut visible = tab.visible();
+                visible.rotate_left(tab.focus);
+                for i in visible {
+                    if self.views[i].1.on_key_event(key) {
+                        return self.views[i].1.take_jump();
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn previous_tab(&mut self) {
+        if self.tabs.is_empty() {
+            return;
+        }
+        self.selected_tab = (self.selected_tab + self.tabs.len() - 1) % self.tabs.len();
+    }
+
+    fn next_tab(&mut self) {
+        if self.tabs.is_empty() {
+            return;
         }
+        self.selected_tab = (self.selected_tab + 1) % self.tabs.len();
     }
 
-    pub fn new(snapshots: &Snapshots) -> Self {
-        let mut rs = None;
-        let mut rob = None;
-        let mut bstack = None;
-        let mut btb = None;
-        let mut issue = None;
-        let mut dcache = None;
-        let mut fu = None;
-        let mut fetch = None;
-        let mut icache = None;
-        let mut regfile = None;
-        let mut store_queue = None;
-        let mut memunit = None;
-        let mut complete = None;
-        let mut is_cpu = false;
-
-        let base = snapshots.get_base();
-        let testbench = snapshots.header.find_scope(&[base.clone()]).unwrap();
-
-        for scope_item in testbench.items.iter() {
-            let ScopeItem::Scope(scope) = scope_item else {
+    fn previous_instance(&mut self) {
+        let Some(tab) = self.tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        let n = tab.num_instances();
+        tab.instance = (tab.instance + n - 1) % n;
+    }
+
+    fn next_instance(&mut self) {
+        let Some(tab) = self.tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        tab.instance = (tab.instance + 1) % tab.num_instances();
+    }
+
+    fn next_focus(&mut self) {
+        let Some(tab) = self.tabs.get_mut(self.selected_tab) else {
+            return;
+        };
+        tab.focus = (tab.focus + 1) % tab.slots.len();
+    }
+
+    /// Base of the view and (ROB number, PC) of the selected instruction on the current tab,
+    /// preferring the focused view.
+    pub fn selected_inst(&self, snapshots: &Snapshots) -> Option<(String, usize, usize)> {
+        let tab = self.tabs.get(self.selected_tab)?;
+        let mut visible = tab.visible();
+        visible.rotate_left(tab.focus);
+        visible.into_iter().find_map(|i| {
+            let view = &self.views[i].1;
+            let (rob_num, pc) = view.selected_inst(snapshots)?;
+            Some((view.base().to_owned(), rob_num, pc))
+        })
+    }
+
+    /// Shows the generic array table for `base`. Returns false if `base` has no indexed sub-scopes.
+    pub fn show_table(&mut self, base: &str, snapshots: &Snapshots) -> bool {
+        let Some(table) = ArrayTable::new(base, snapshots) else {
+            return false;
+        };
+
+        // only keep one table around, replacing the last one
+        if let Some(i) = self.views.iter().position(|(marker, _)| *marker == "table") {
+            self.views[i].1 = Box::new(table);
+            self.selected_tab = self
+                .tabs
+                .iter()
+                .position(|tab| tab.slots == [vec![i]])
+                .unwrap_or(0);
+        } else {
+            self.views.push(("table", Box::new(table)));
+            self.tabs.push(Tab::new(
+                String::from("Table"),
+                vec![vec![self.views.len() - 1]],
+            ));
+            self.selected_tab = self.tabs.len() - 1;
+        }
+        true
+    }
+
+    /// Finds every structure in the trace. `program` is what the testbench loaded, if given, and
+    /// `retire_log` is shared with whatever else needs the first core's retirement stream.
+    pub fn new(
+        snapshots: &Snapshots,
+        program: Option<&MemoryImage>,
+        retire_log: Rc<OnceCell<Option<RetireLog>>>,
+    ) -> Self {
+        let discovery = Discovery {
+            snapshots,
+            program,
+            markers: find_markers(&snapshots.header),
+            perf_trace: Rc::new(OnceCell::new()),
+            retire_log,
+        };
+        let is_cpu = discovery.has(&["cpu"]);
+
+        // views of marked modules in the order they're in the design, then views built from them
+        let mut views: Vec<(&'static str, Box<dyn StructureView>)> = Vec::new();
+        for (marker, base) in discovery.markers.iter() {
+            let Some(kind) = REGISTRY
+                .iter()
+                .find(|kind| kind.marker == marker && matches!(kind.source, Source::Marker))
+            else {
                 continue;
             };
-            let new_base = format!("{base}.{}", scope.identifier);
-
-            let cpu_var = format!("{new_base}.dbg_this_is_cpu");
-            is_cpu = snapshots.get_var(&cpu_var).is_some();
-
-            if is_cpu {
-                // get all the cpu paths
-                rs = RSTable::new(&format!("{new_base}.rs_module"), snapshots);
-                rob = ROBTable::new(&format!("{new_base}.rob_module"), snapshots);
-                bstack = BranchStack::new(&format!("{new_base}.branch_stack_module"), snapshots);
-                btb = Btb::new(&format!("{new_base}.btb"), snapshots);
-                issue = Issue::new(&format!("{new_base}.issue_module"), snapshots);
-                regfile = RegFile::new(&format!("{new_base}.regfile_module"), snapshots);
-                dcache = DCache::new(&format!("{new_base}.dcache_module"), snapshots);
-                icache = ICache::new(&format!("{new_base}.fetch_module.icache_module"), snapshots);
-                fetch = Fetch::new(&format!("{new_base}.fetch_module"), snapshots);
-                store_queue = StoreQueue::new(&format!("{new_base}.store_queue_module"), snapshots);
-                memunit = MemUnit::new(&format!("{new_base}.memunit_module"), snapshots);
-                fu = FU::new(&format!("{new_base}.fu_module"), snapshots);
-                complete = Complete::new(&format!("{new_base}.complete_module"), snapshots);
-
-                break;
-            } else {
-                // try to fit each module into rs or rob if they match the shape
-                if rs.is_none() {
-                    rs = RSTable::new(&new_base, snapshots);
-                }
-                if rob.is_none() {
-                    rob = ROBTable::new(&new_base, snapshots);
-                }
-                if bstack.is_none() {
-                    bstack = BranchStack::new(&new_base, snapshots);
-                }
-                if btb.is_none() {
-                    btb = Btb::new(&new_base, snapshots);
-                }
-                if issue.is_none() {
-                    issue = Issue::new(&new_base, snapshots);
-                }
-                if dcache.is_none() {
-                    dcache = DCache::new(&new_base, snapshots);
-                }
-                if icache.is_none() {
-                    icache = ICache::new(&new_base, snapshots);
-                }
-                if fetch.is_none() {
-                    fetch = Fetch::new(&new_base, snapshots);
-                }
-                if regfile.is_none() {
-                    regfile = RegFile::new(&new_base, snapshots);
+            if let Some(view) = (kind.discover)(base, &discovery) {
+                views.push((kind.marker, view));
+            }
+        }
+        for kind in REGISTRY.iter() {
+            if matches!(kind.source, Source::Marker) {
+                continue;
+            }
+            for base in discovery.sources(kind) {
+                if let Some(view) = (kind.discover)(&base, &discovery) {
+                    views.push((kind.marker, view));
                 }
-                if store_queue.is_none() {
-                    store_queue = StoreQueue::new(&new_base, snapshots);
+            }
+        }
+
+        // every instance of a kind of view, in the order they were found
+        let instances_of = |marker: &str| -> Vec<usize> {
+            (0..views.len()).filter(|&i| views[i].0 == marker).collect()
+        };
+        let tab_of = |marker: &str| {
+            REGISTRY
+                .iter()
+                .find(|kind| kind.marker == marker)
+                .and_then(|kind| kind.tab)
+        };
+
+        let mut tabs = Vec::new();
+        if is_cpu {
+            let mut names: Vec<&str> = Vec::new();
+            for name in REGISTRY.iter().filter_map(|kind| kind.tab) {
+                if !names.contains(&name) {
+                    names.push(name);
                 }
-                if memunit.is_none() {
-                    memunit = MemUnit::new(&new_base, snapshots);
+            }
+            for name in names {
+                let slots: Vec<Vec<usize>> = REGISTRY
+                    .iter()
+                    .filter(|kind| kind.tab == Some(name))
+                    .map(|kind| instances_of(kind.marker))
+                    .filter(|slot| !slot.is_empty())
+                    .collect();
+                if !slots.is_empty() {
+                    tabs.push(Tab::new(name.to_owned(), slots));
                 }
-                if fu.is_none() {
-                    fu = FU::new(&new_base, snapshots);
+            }
+            for (i, (marker, view)) in views.iter().enumerate() {
+                if instances_of(marker)[0] == i && tab_of(marker).is_none() {
+                    tabs.push(Tab::new(view.name(), vec![instances_of(marker)]));
                 }
-                if complete.is_none() {
-                    complete = Complete::new(&new_base, snapshots);
+            }
+        } else {
+            // module testbenches: every kind of structure gets its own tab, plus one with all of
+            // them side by side if there's more than one
+            let mut slots: Vec<Vec<usize>> = Vec::new();
+            for (i, (marker, _)) in views.iter().enumerate() {
+                if instances_of(marker)[0] == i {
+                    slots.push(instances_of(marker));
                 }
             }
+            if slots.len() > 1 {
+                tabs.push(Tab::new(String::from("All"), slots.clone()));
+            }
+            for slot in slots {
+                tabs.push(Tab::new(views[slot[0]].1.name(), vec![slot]));
+            }
         }
 
+        let selected_tab = tabs
+            .iter()
+            .position(|tab| tab.name == DEFAULT_TAB)
+            .unwrap_or(0);
+
         Self {
-            rs,
-            rob,
-            bstack,
-            btb,
-            is_cpu,
-            issue,
-            dcache,
-            icache,
-            fetch,
-            regfile,
-            store_queue,
-            memunit,
-            complete,
-            fu,
-            selected_tab: SelectedTab::default(),
+            views,
+            tabs,
+            selected_tab,
         }
     }
-}
-
-// ai generated(gemini)
-fn split_rectangle_horizontal(area: Rect) -> Vec<Rect> {
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-        .split(area)
-        .to_vec()
-}
-
-impl StatefulWidget for Structures {
-    type State = Snapshots;
 
-    fn render(
-        self,
-        area: ratatui::prelude::Rect,
-        buf: &mut ratatui::prelude::Buffer,
-        state: &mut Self::State,
-    ) {
-        if self.is_cpu {
+    pub fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let inner_area = if !self.tabs.is_empty() {
             use Constraint::{Length, Min};
             let vertical = Layout::vertical([Length(1), Min(0)]);
             let [header_area, inner_area] = vertical.areas(area);
@@ -253,78 +387,209 @@ impl StatefulWidget for Structures {
             let [tabs_area, _title_area] = horizontal.areas(header_area);
 
             self.render_tabs(tabs_area, buf);
+            inner_area
+        } else {
+            area
+        };
 
-            match self.selected_tab {
-                SelectedTab::RsRob => {
-                    let areas = split_rectangle_horizontal(inner_area);
-                    self.rs.unwrap().render(areas[0], buf, state);
-                    self.rob.unwrap().render(areas[1], buf, state);
-                }
-                SelectedTab::BStack => {
-                    let areas = split_rectangle_horizontal(inner_area);
-                    if let Some(btb) = self.btb {
-                        let [top_area, bottom_area] = Layout::vertical([
-                            Constraint::Length(btb.size as u16 + 1 + 2),
-                            Constraint::Fill(1),
-                        ])
-                        .areas(inner_area);
-
-                        btb.render(top_area, buf, state);
-                        let bottom_areas = split_rectangle_horizontal(bottom_area);
-                        self.bstack.unwrap().render(bottom_areas[0], buf, state);
-                        self.regfile.unwrap().render(bottom_areas[1], buf, state);
-                    } else {
-                        self.bstack.unwrap().render(areas[0], buf, state);
-                        self.rob.unwrap().render(areas[1], buf, state);
+        let Some(tab) = self.tabs.get(self.selected_tab) else {
+            Paragraph::new(Line::from(
+                "No structures found! Press <t> to show any scope as a table",
+            ))
+            .centered()
+            .render(inner_area, buf);
+            return;
+        };
+
+        let visible = tab.visible();
+        let views: Vec<&dyn StructureView> =
+            visible.iter().map(|&i| self.views[i].1.as_ref()).collect();
+        for (slot, ((&i, view), area)) in visible
+            .iter()
+            .zip(views.iter())
+            .zip(layout_views(&views, inner_area))
+            .enumerate()
+        {
+            render_checked(*view, area, buf, snapshots);
+
+            if slot == tab.focus && visible.len() > 1 {
+                buf.set_style(Rect::new(area.x, area.y, area.width, 1), Color::Blue);
+            }
+
+            // label views that have siblings with where they are, so they can be told apart
+            let marker = self.views[i].0;
+            if self.views.iter().filter(|(m, _)| *m == marker).count() > 1 {
+                render_path_label(view.base(), area, buf);
+            }
+        }
+    }
+}
+
+/// Every `dbg_this_is_<marker>` signal in the design as (marker, path of the scope it's in), at any
+/// depth, in the order they appear in the vcd file. Map tables don't need the marker: if there
+/// isn't one, any scope with a `map_table[]` array that isn't inside another marked module (like
+/// a branch stack's checkpoints) is taken as one.
+fn find_markers(header: &Header) -> Vec<(String, String)> {
+    fn walk(
+        path: &str,
+        scope: &Scope,
+        markers: &mut Vec<(String, String)>,
+        map_tables: &mut Vec<String>,
+    ) {
+        for item in scope.items.iter() {
+            match item {
+                ScopeItem::Var(var) => {
+                    if let Some(marker) = var.reference.strip_prefix("dbg_this_is_") {
+                        markers.push((marker.to_owned(), path.to_owned()));
                     }
                 }
-                SelectedTab::IssueFUs => {
-                    let [left_area, right_area] = split_horizontal(inner_area);
-                    let [top_left_area, bottom_left_area] = split_vertical(left_area);
-                    let [top_right_area, bottom_right_area] = split_vertical(right_area);
-
-                    self.issue.unwrap().render(top_left_area, buf, state);
-                    self.fu.unwrap().render(top_right_area, buf, state);
-                    self.complete.unwrap().render(bottom_left_area, buf, state);
-                    self.memunit.unwrap().render(bottom_right_area, buf, state);
-                }
-                SelectedTab::Memory => {
-                    let areas = split_rectangle_horizontal(inner_area);
-                    self.dcache.unwrap().render(areas[0], buf, state);
-                    // self.icache.unwrap().render(areas[1], buf, state);
-                    self.store_queue.unwrap().render(areas[1], buf, state);
-                }
-                SelectedTab::Fetch => {
-                    let areas = split_rectangle_horizontal(inner_area);
-                    self.fetch.unwrap().render(areas[0], buf, state);
-                    self.icache.unwrap().render(areas[1], buf, state);
+                ScopeItem::Scope(sub) => {
+                    if sub.identifier == "map_table[0]" {
+                        map_tables.push(path.to_owned());
+                    }
+                    walk(
+                        &format!("{path}.{}", sub.identifier),
+                        sub,
+                        markers,
+                        map_tables,
+                    );
                 }
+                _ => {}
             }
-        } else {
-            // assumption: just a single module test (though this could change in the future)
-            if let Some(rs) = self.rs {
-                rs.render(area, buf, state);
-            } else if let Some(rob) = self.rob {
-                rob.render(area, buf, state);
-            } else if let Some(bstack) = self.bstack {
-                bstack.render(area, buf, state);
-            } else if let Some(btb) = self.btb {
-                btb.render(area, buf, state);
-            } else if let Some(issue) = self.issue {
-                issue.render(area, buf, state);
-            } else if let Some(dcache) = self.dcache {
-                dcache.render(area, buf, state);
-            } else if let Some(icache) = self.icache {
-                icache.render(area, buf, state);
-            } else if let Some(regfile) = self.regfile {
-                regfile.render(area, buf, state);
-            } else if let Some(memunit) = self.memunit {
-                memunit.render(area, buf, state);
-            } else if let Some(store_queue) = self.store_queue {
-                store_queue.render(area, buf, state);
-            } else if let Some(complete) = self.complete {
-                complete.render(area, buf, state);
-            }
         }
     }
+
+    let mut markers = Vec::new();
+    let mut map_tables = Vec::new();
+    for item in header.items.iter() {
+        let ScopeItem::Scope(scope) = item else {
+            continue;
+        };
+        if scope.identifier.starts_with("_vcs") {
+            continue;
+        }
+        walk(&scope.identifier, scope, &mut markers, &mut map_tables);
+    }
+
+    if !markers.iter().any(|(marker, _)| marker == "map_table") {
+        let inside_marked = |path: &str| {
+            markers.iter().any(|(marker, base)| {
+                marker != "cpu" && path.starts_with(base) && path[base.len()..].starts_with('.')
+            })
+        };
+        let found: Vec<String> = map_tables
+            .into_iter()
+            .filter(|path| !inside_marked(path))
+            .collect();
+        markers.extend(
+            found
+                .into_iter()
+                .map(|path| (String::from("map_table"), path)),
+        );
+    }
+    markers
+}
+
+/// Path of the `marker` module closest to `base` in the hierarchy, e.g. the regfile in the same
+/// core as a map table when there's more than one core.
+pub fn nearest_marker(header: &Header, marker: &str, base: &str) -> Option<String> {
+    let shared_len = |path: &str| {
+        path.split('.')
+            .zip(base.split('.'))
+            .take_while(|(a, b)| a == b)
+            .count()
+    };
+
+    find_markers(header)
+        .into_iter()
+        .filter(|(m, _)| m == marker)
+        .map(|(_, path)| path)
+        // max_by_key keeps the last of equally close paths, we want the first
+        .rev()
+        .max_by_key(|path| shared_len(path))
+}
+
+/// Splits `area` into a grid with one cell per view. If the first view asks for a fixed height,
+/// it gets a full-width row on top instead.
+fn layout_views(views: &[&dyn StructureView], area: Rect) -> Vec<Rect> {
+    if let (Some(height), true) = (views[0].height_hint(), views.len() > 1) {
+        let [top_area, bottom_area] =
+            Layout::vertical([Constraint::Length(height), Constraint::Fill(1)]).areas(area);
+        let mut areas = vec![top_area];
+        areas.extend(layout_views(&views[1..], bottom_area));
+        return areas;
+    }
+
+    let num_cols = (views.len() as f64).sqrt().ceil() as usize;
+    let num_rows = views.len().div_ceil(num_cols);
+
+    let rows = Layout::vertical(vec![Constraint::Fill(1); num_rows]).split(area);
+    rows.iter()
+        .flat_map(|row| {
+            Layout::horizontal(vec![Constraint::Fill(1); num_cols])
+                .split(*row)
+                .to_vec()
+        })
+        .take(views.len())
+        .collect()
+}
+
+/// Writes `path` into the right side of the top border of `area`.
+fn render_path_label(path: &str, area: Rect, buf: &mut Buffer) {
+    if area.height == 0 || area.width <= 4 {
+        return;
+    }
+    let label = Line::from(format!(" {path} ")).magenta().right_aligned();
+    label.render(Rect::new(area.x + 1, area.y, area.width - 2, 1), buf);
+}
+
+/// Renders `view`, then lists any signals it asked for but couldn't find along the bottom
+/// border of `area` so a renamed wire shows up as a diagnostic instead of a crash.
+fn render_checked(
+    view: &dyn StructureView,
+    area: Rect,
+    buf: &mut Buffer,
+    snapshots: &mut Snapshots,
+) {
+    snapshots.take_missing();
+    view.render(area, buf, snapshots);
+
+    let missing = snapshots.take_missing();
+    if missing.is_empty() || area.height == 0 || area.width <= 2 {
+        return;
+    }
+
+    // entries[0].t, entries[1].t, ... all collapse into entries[*].t
+    let base = view.base().to_owned() + ".";
+    let mut names: Vec<String> = missing
+        .iter()
+        .map(|name| {
+            let name = name.strip_prefix(&base).unwrap_or(name);
+            let mut collapsed = String::with_capacity(name.len());
+            let mut in_index = false;
+            for c in name.chars() {
+                match c {
+                    '[' => {
+                        in_index = true;
+                        collapsed.push_str("[*");
+                    }
+                    ']' => {
+                        in_index = false;
+                        collapsed.push(']');
+                    }
+                    _ if in_index => {}
+                    _ => collapsed.push(c),
+                }
+            }
+            collapsed
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let diagnostic = Line::from(format!(" missing: {} ", names.join(", ")))
+        .red()
+        .bold();
+    let bottom = Rect::new(area.x + 1, area.bottom() - 1, area.width - 2, 1);
+    diagnostic.render(bottom, buf);
 }
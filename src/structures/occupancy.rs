@@ -0,0 +1,181 @@
+use std::{cell::OnceCell, ops::Range, rc::Rc};
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::Marker,
+    text::Line,
+    widgets::{Axis, Block, Chart, Dataset, GraphType, Widget},
+};
+
+use crate::{
+    perf::{PerfTrace, Sample},
+    retire::RetireLog,
+    snapshots::Snapshots,
+};
+
+use super::{
+    perf::{collect_trace, Window},
+    Discovery, StructureView,
+};
+
+/// ROB, RS and store queue occupancy plotted over the cycles around the cursor.
+pub struct Occupancy {
+    base: String,
+    /// shared with the "Perf" tab, collected the first time either is shown
+    trace: Rc<OnceCell<PerfTrace>>,
+    /// what the core retired, which the counters are collected from
+    retire_log: Rc<OnceCell<Option<RetireLog>>>,
+    /// of the cycles plotted, which are around the cursor
+    window: Window,
+}
+
+impl Occupancy {
+    pub fn new(
+        base: &str,
+        trace: Rc<OnceCell<PerfTrace>>,
+        retire_log: Rc<OnceCell<Option<RetireLog>>>,
+    ) -> Self {
+        Self {
+            base: base.to_owned(),
+            trace,
+            retire_log,
+            window: Window::default(),
+        }
+    }
+}
+
+/// One structure's chart.
+struct Series {
+    name: &'static str,
+    capacity: usize,
+    occupancy: fn(&Sample) -> usize,
+}
+
+impl Series {
+    /// `None` if the design doesn't have the structure.
+    fn new(
+        name: &'static str,
+        capacity: Option<usize>,
+        occupancy: fn(&Sample) -> usize,
+    ) -> Option<Self> {
+        Some(Self {
+            name,
+            capacity: capacity?,
+            occupancy,
+        })
+    }
+
+    /// Occupancy over `cycles`, with the cursor as a vertical line.
+    fn render(
+        &self,
+        trace: &PerfTrace,
+        cycles: Range<usize>,
+        current: usize,
+        area: Rect,
+        buf: &mut Buffer,
+    ) {
+        let Range { start, end } = cycles;
+        let Self {
+            name,
+            capacity,
+            occupancy,
+        } = *self;
+        let points: Vec<(f64, f64)> = (start..end)
+            .map(|i| (i as f64, occupancy(&trace.samples[i]) as f64))
+            .collect();
+        let cursor = [(current as f64, 0.0), (current as f64, capacity as f64)];
+
+        let datasets = vec![
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(Color::Cyan))
+                .data(&points),
+            Dataset::default()
+                .marker(Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::new().fg(Color::Yellow))
+                .data(&cursor),
+        ];
+
+        let clock = |i: usize| trace.clocks.get(i).copied().unwrap_or(0).to_string();
+        let x_axis = Axis::default()
+            .bounds([start as f64, end.saturating_sub(1).max(start + 1) as f64])
+            .labels([clock(start), clock(end.saturating_sub(1))]);
+        let y_axis = Axis::default()
+            .bounds([0.0, capacity as f64])
+            .labels([String::from("0"), capacity.to_string()]);
+
+        let now = occupancy(&trace.samples[current]);
+        let title = Line::from(format!("{name}: {now}/{capacity}")).bold();
+        Chart::new(datasets)
+            .block(Block::bordered().title(title))
+            .x_axis(x_axis)
+            .y_axis(y_axis)
+            .render(area, buf);
+    }
+}
+
+impl StructureView for Occupancy {
+    /// Needs the whole core's counters, so it's added alongside the "Perf" tab instead.
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        discovery.has(&["rob", "rs", "store_queue"]).then(|| {
+            Self::new(
+                base,
+                discovery.perf_trace.clone(),
+                discovery.retire_log.clone(),
+            )
+        })
+    }
+
+    fn name(&self) -> String {
+        String::from("Occupancy")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        self.window.on_key_event(key)
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let trace = collect_trace(&self.trace, &self.retire_log, &self.base, snapshots);
+        let num_cycles = trace.samples.len();
+        let current = snapshots.cycle().min(num_cycles.saturating_sub(1));
+
+        // the window is centered on the cursor, unless that'd run off either end of the trace
+        let window = self.window.cycles().min(num_cycles);
+        let end = (current + window / 2 + 1).max(window).min(num_cycles);
+        let start = end - window;
+
+        let charts: Vec<Series> = [
+            Series::new("ROB", trace.capacity.rob, |s| s.rob),
+            Series::new("RS", trace.capacity.rs, |s| s.rs),
+            Series::new("Store Queue", trace.capacity.sq, |s| s.sq),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+
+        let block = Block::bordered()
+            .title(Line::from("Occupancy").bold().centered())
+            .title_bottom(
+                Line::from(format!(" <W> window size ({}) ", self.window.name())).centered(),
+            );
+        let inner = block.inner(area);
+        block.render(area, buf);
+        if num_cycles == 0 {
+            return;
+        }
+
+        let areas = Layout::vertical(vec![Constraint::Fill(1); charts.len()]).split(inner);
+        for (chart, &area) in charts.iter().zip(areas.iter()) {
+            chart.render(trace, start..end, current, area, buf);
+        }
+    }
+}
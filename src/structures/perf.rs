@@ -0,0 +1,147 @@
+use std::{cell::OnceCell, rc::Rc};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Row, Table, Widget},
+};
+
+use crate::{perf::PerfTrace, retire::RetireLog, snapshots::Snapshots};
+
+use super::{Discovery, StructureView};
+
+/// Window sizes that <W> cycles through, in cycles. The last is the whole trace.
+const WINDOWS: [usize; 4] = [100, 1000, 10000, usize::MAX];
+
+/// How many cycles near the cursor a view looks at, picked with <W>. Shared with the "Occupancy"
+/// tab.
+#[derive(Clone, Copy, Debug, Default)]
+pub(super) struct Window(usize);
+
+impl Window {
+    /// Moves to the next size with <W>. Returns true if the key was it.
+    pub(super) fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('W') => self.0 = (self.0 + 1) % WINDOWS.len(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Size in cycles, `usize::MAX` for the whole trace.
+    pub(super) fn cycles(self) -> usize {
+        WINDOWS[self.0]
+    }
+
+    /// The size for the <W> hint.
+    pub(super) fn name(self) -> String {
+        match self.cycles() {
+            usize::MAX => String::from("whole trace"),
+            size => format!("{size} cycles"),
+        }
+    }
+}
+
+/// Performance counters over the whole trace next to the ones for the last few cycles.
+pub struct PerfView {
+    base: String,
+    /// collected the first time it's shown, since it has to go through the whole trace
+    trace: Rc<OnceCell<PerfTrace>>,
+    /// what the core retired, which the counters are collected from
+    retire_log: Rc<OnceCell<Option<RetireLog>>>,
+    /// of the recent counters, which end at the cursor
+    window: Window,
+}
+
+impl PerfView {
+    pub fn new(
+        base: &str,
+        trace: Rc<OnceCell<PerfTrace>>,
+        retire_log: Rc<OnceCell<Option<RetireLog>>>,
+    ) -> Self {
+        Self {
+            base: base.to_owned(),
+            trace,
+            retire_log,
+            window: Window::default(),
+        }
+    }
+}
+
+/// Collects the counters of the core at `base` the first time any of the views sharing `trace`
+/// is shown.
+pub(super) fn collect_trace<'a>(
+    trace: &'a OnceCell<PerfTrace>,
+    retire_log: &OnceCell<Option<RetireLog>>,
+    base: &str,
+    snapshots: &mut Snapshots,
+) -> &'a PerfTrace {
+    trace.get_or_init(|| {
+        let log = retire_log.get_or_init(|| RetireLog::extract_at(base, snapshots));
+        PerfTrace::collect(base, log.as_ref(), snapshots)
+    })
+}
+
+impl StructureView for PerfView {
+    /// Counters come from whatever structures the core has, but they need it to retire something.
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        RetireLog::available(base, discovery.snapshots).then(|| {
+            Self::new(
+                base,
+                discovery.perf_trace.clone(),
+                discovery.retire_log.clone(),
+            )
+        })
+    }
+
+    fn name(&self) -> String {
+        String::from("Perf")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        self.window.on_key_event(key)
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let trace = collect_trace(&self.trace, &self.retire_log, &self.base, snapshots);
+
+        let current = snapshots.cycle();
+        let window = self.window.cycles();
+        let whole = trace.summarize(0..trace.samples.len());
+        let recent = trace.summarize((current + 1).saturating_sub(window)..current + 1);
+
+        // both summaries have the same counters, since they come from the same structures
+        let rows = whole
+            .rows()
+            .into_iter()
+            .zip(recent.rows())
+            .map(|((name, whole), (_, recent))| Row::new(vec![name.to_owned(), whole, recent]));
+
+        let header = Row::new(vec![
+            String::from("counter"),
+            String::from("whole trace"),
+            match window {
+                usize::MAX => String::from("up to the cursor"),
+                _ => format!("last {window} cycles"),
+            },
+        ]);
+        let widths = [
+            Constraint::Length(14),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+        ];
+        let title = Line::from("Performance Counters").bold().centered();
+        let hint = Line::from(format!(" <W> window size ({}) ", self.window.name())).centered();
+        Table::new(rows, widths)
+            .header(header.bold().on_blue())
+            .block(Block::bordered().title(title).title_bottom(hint))
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,216 @@
+use std::{cell::RefCell, ops::Range};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
+};
+
+use crate::{
+    lifetime::{Fate, Pipeline, Stage, Trace},
+    snapshots::Snapshots,
+};
+
+use super::{Discovery, StructureView};
+
+/// width of the column with the instruction in it
+const LABEL_WIDTH: u16 = 32;
+/// width of one cycle
+const CELL_WIDTH: u16 = 2;
+
+fn stage_color(stage: Stage) -> Color {
+    match stage {
+        Stage::Fetch => Color::Cyan,
+        Stage::Rs => Color::Yellow,
+        Stage::Issue => Color::Magenta,
+        Stage::Fu => Color::Blue,
+        Stage::Complete => Color::Green,
+        Stage::Rob => Color::LightGreen,
+    }
+}
+
+/// Konata-style diagram of the instructions in flight around the cursor: one row per dynamic
+/// instruction, one column per cycle, with the letter of the stage it was in.
+pub struct PipelineDiagram {
+    base: String,
+    pipeline: Pipeline,
+    cycle_indices: Vec<usize>,
+    /// last trace built and the cycles it covers, since it only changes when the cursor moves
+    trace: RefCell<Option<(Range<usize>, Trace)>>,
+}
+
+impl PipelineDiagram {
+    pub fn new(base: &str, snapshots: &Snapshots) -> Option<Self> {
+        snapshots.get_var(&format!("{base}.dbg_this_is_rob"))?;
+
+        Some(Self {
+            base: base.to_owned(),
+            pipeline: Pipeline::new(base, snapshots)?,
+            cycle_indices: snapshots.cycle_indices(),
+            trace: RefCell::new(None),
+        })
+    }
+
+    fn get_header(&self, trace: &Trace, current: usize) -> Line<'_> {
+        let mut spans =
+            vec![Span::from(format!("{:<width$}", "cycle", width = LABEL_WIDTH as usize)).bold()];
+
+        let mut skip = 0;
+        for (i, clock) in trace.clocks.iter().enumerate() {
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let cycle = trace.cycles.start + i;
+            let text = if cycle == current || clock % 5 == 0 {
+                clock.to_string()
+            } else {
+                String::new()
+            };
+            // numbers wider than a cell take up the cells after them
+            let cells = (text.len() as u16).div_ceil(CELL_WIDTH).max(1);
+            skip = cells as usize - 1;
+            let text = format!("{text:<width$}", width = (cells * CELL_WIDTH) as usize);
+
+            let span = Span::from(text);
+            spans.push(if cycle == current {
+                span.bold().reversed()
+            } else {
+                span.bold()
+            });
+        }
+        Line::from(spans)
+    }
+}
+
+impl StructureView for PipelineDiagram {
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        Self::new(base, discovery.snapshots)
+    }
+
+    fn name(&self) -> String {
+        String::from("Pipeline")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let title = Line::from("Pipeline").bold().centered();
+        let legend = Line::from(vec![
+            " F".fg(stage_color(Stage::Fetch)),
+            " fetch ".into(),
+            "D".fg(stage_color(Stage::Rs)),
+            " dispatch ".into(),
+            "I".fg(stage_color(Stage::Issue)),
+            " issue ".into(),
+            "X".fg(stage_color(Stage::Fu)),
+            " execute ".into(),
+            "C".fg(stage_color(Stage::Complete)),
+            " complete ".into(),
+            "R".fg(stage_color(Stage::Rob)),
+            " retire ".into(),
+        ])
+        .centered();
+        let block = Block::bordered().title(title).title_bottom(legend);
+        let inner_area = block.inner(area);
+        block.render(area, buf);
+
+        if inner_area.width <= LABEL_WIDTH || inner_area.height < 2 || self.cycle_indices.is_empty()
+        {
+            return;
+        }
+
+        // keep most of the window on the past, that's what's interesting when debugging
+        let num_cycles = ((inner_area.width - LABEL_WIDTH) / CELL_WIDTH) as usize;
+        let current = self
+            .cycle_indices
+            .iter()
+            .position(|&i| i >= snapshots.index())
+            .unwrap_or(self.cycle_indices.len() - 1);
+        let start = current.saturating_sub(num_cycles * 2 / 3);
+        let cycles = start..start + num_cycles;
+        let mut cached = self.trace.borrow_mut();
+        if cached.as_ref().is_some_and(|(built, _)| *built != cycles) {
+            *cached = None;
+        }
+        let (_, trace) = cached.get_or_insert_with(|| {
+            let trace = Trace::build(
+                &self.pipeline,
+                snapshots,
+                &self.cycle_indices,
+                cycles.clone(),
+            );
+            (cycles, trace)
+        });
+
+        // scroll so the oldest instruction still in flight is near the top
+        let num_rows = (inner_area.height - 1) as usize;
+        let oldest = trace
+            .insts
+            .iter()
+            .position(|inst| inst.end() >= current)
+            .unwrap_or(trace.insts.len());
+        let first_row = oldest
+            .saturating_sub(2)
+            .min(trace.insts.len().saturating_sub(num_rows));
+
+        let mut lines = vec![self.get_header(trace, current)];
+
+        for traced in trace.insts.iter().skip(first_row).take(num_rows) {
+            let label = format!("#{:<2} {}", traced.inst.rob_num, traced.disasm);
+            let mut label = format!("{label:<width$.width$}", width = LABEL_WIDTH as usize - 1);
+            label.push(' ');
+            let mut spans = vec![if traced.fate == Fate::Squashed {
+                Span::from(label).crossed_out()
+            } else {
+                Span::from(label)
+            }];
+
+            for cycle in trace.cycles.clone() {
+                let mut span = match traced.stage_at(cycle) {
+                    Some((stage, seen)) => {
+                        let is_last = cycle == traced.end();
+                        let letter = if is_last && traced.fate == Fate::Retired {
+                            Stage::Rob.letter()
+                        } else if seen {
+                            stage.letter()
+                        } else {
+                            // still waiting on the next stage
+                            stage.letter().to_ascii_lowercase()
+                        };
+                        let style = Style::new().fg(if traced.fate == Fate::Squashed {
+                            Color::Red
+                        } else if is_last && traced.fate == Fate::Retired {
+                            stage_color(Stage::Rob)
+                        } else {
+                            stage_color(stage)
+                        });
+
+                        let span = Span::styled(
+                            format!("{letter:<width$}", width = CELL_WIDTH as usize),
+                            style,
+                        );
+                        if seen || is_last {
+                            span.bold()
+                        } else {
+                            span.dim()
+                        }
+                    }
+                    None => Span::from(" ".repeat(CELL_WIDTH as usize)),
+                };
+                if cycle == current {
+                    span = span.on_dark_gray();
+                }
+                spans.push(span);
+            }
+
+            lines.push(Line::from(spans));
+        }
+
+        Paragraph::new(lines).render(inner_area, buf);
+    }
+}
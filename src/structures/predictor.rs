@@ -0,0 +1,239 @@
+use std::{
+    cell::{Cell, OnceCell},
+    cmp::Reverse,
+};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, Paragraph, Row, StatefulWidget, Table, TableState, Widget},
+};
+
+use crate::{
+    perf::percent,
+    predictor::{BranchHistory, BranchStats},
+    snapshots::{Snapshots, VerilogValue},
+};
+
+use super::{view::select_row, Discovery, StructureView};
+
+const HEADERS: [&str; 8] = [
+    "pc", "executed", "taken", "mispred", "accuracy", "BTB hits", "PHT", "aliases",
+];
+const WIDTHS: [Constraint; 8] = [
+    Constraint::Fill(2),
+    Constraint::Length(8),
+    Constraint::Length(6),
+    Constraint::Length(7),
+    Constraint::Length(8),
+    Constraint::Length(9),
+    Constraint::Length(6),
+    Constraint::Fill(1),
+];
+
+/// What the branch table is sorted by, cycled with <S>.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SortBy {
+    Mispredicts,
+    Accuracy,
+    Executed,
+    Pc,
+}
+
+impl SortBy {
+    fn next(self) -> Self {
+        match self {
+            SortBy::Mispredicts => SortBy::Accuracy,
+            SortBy::Accuracy => SortBy::Executed,
+            SortBy::Executed => SortBy::Pc,
+            SortBy::Pc => SortBy::Mispredicts,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SortBy::Mispredicts => "mispredicts",
+            SortBy::Accuracy => "accuracy",
+            SortBy::Executed => "executions",
+            SortBy::Pc => "pc",
+        }
+    }
+}
+
+/// Per-branch prediction accuracy, BTB hits and PHT aliasing, worst-predicted branches first.
+pub struct Predictor {
+    base: String,
+    /// extracted the first time it's shown, since it has to go through the whole trace
+    history: OnceCell<BranchHistory>,
+    sort_by: SortBy,
+    /// row selected with <J/K>, in the current sort order
+    selected: Option<usize>,
+    /// cycle the view was last shown at, to jump to mispredicts relative to
+    cursor: Cell<usize>,
+    jump: Option<usize>,
+}
+
+impl Predictor {
+    /// `base` is the FU with the branch output packets.
+    pub fn new(base: &str, snapshots: &Snapshots) -> Option<Self> {
+        snapshots.get_scope(&format!("{base}.branch_output_packets[0]"))?;
+        Some(Self {
+            base: base.to_owned(),
+            history: OnceCell::new(),
+            sort_by: SortBy::Mispredicts,
+            selected: None,
+            cursor: Cell::new(0),
+            jump: None,
+        })
+    }
+
+    /// Branches in the order they're shown.
+    fn sorted<'a>(&self, history: &'a BranchHistory) -> Vec<&'a BranchStats> {
+        let mut branches: Vec<&BranchStats> = history.branches.iter().collect();
+        match self.sort_by {
+            SortBy::Mispredicts => branches.sort_by_key(|b| Reverse(b.mispredicted)),
+            SortBy::Accuracy => branches.sort_by(|a, b| a.accuracy().total_cmp(&b.accuracy())),
+            SortBy::Executed => branches.sort_by_key(|b| Reverse(b.executed)),
+            SortBy::Pc => {}
+        }
+        branches
+    }
+}
+
+impl StructureView for Predictor {
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        Self::new(base, discovery.snapshots)
+    }
+
+    fn name(&self) -> String {
+        String::from("Predictor")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        let Some(history) = self.history.get() else {
+            return false;
+        };
+        if select_row(&mut self.selected, history.branches.len(), key) {
+            return true;
+        }
+        match key.code {
+            KeyCode::Char('S') => {
+                self.sort_by = self.sort_by.next();
+                self.selected = None;
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') => {
+                let pc = self
+                    .selected
+                    .and_then(|i| self.sorted(history).get(i).map(|b| b.pc));
+                let forward = key.code == KeyCode::Char('n');
+                self.jump = history.next_mispredict(self.cursor.get(), pc, forward);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn take_jump(&mut self) -> Option<usize> {
+        self.jump.take()
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let history = self
+            .history
+            .get_or_init(|| BranchHistory::extract(&self.base, snapshots));
+        let current = snapshots.cycle();
+        self.cursor.set(current);
+
+        let outcomes = &history.outcomes;
+        let mispredicted = outcomes.iter().filter(|o| o.mispredicted).count();
+        let btb: Vec<bool> = outcomes.iter().filter_map(|o| o.btb_hit).collect();
+        let btb_hits = btb.iter().filter(|&&hit| hit).count();
+        let aliased = history.pht.values().filter(|pcs| pcs.len() > 1).count();
+
+        let mut summary = vec![Line::from(format!(
+            "{} branches resolved at {} PCs, {mispredicted} mispredicted ({} accuracy)",
+            outcomes.len(),
+            history.branches.len(),
+            percent(outcomes.len() - mispredicted, outcomes.len()),
+        ))];
+        if !btb.is_empty() {
+            summary.push(Line::from(format!(
+                "BTB: {btb_hits}/{} taken branches hit ({})",
+                btb.len(),
+                percent(btb_hits, btb.len())
+            )));
+        }
+        if !history.pht.is_empty() {
+            summary.push(Line::from(format!(
+                "PHT: {} entries used, {aliased} shared by more than one branch",
+                history.pht.len()
+            )));
+        }
+
+        // branches resolving this cycle
+        let resolving: Vec<usize> = outcomes
+            .iter()
+            .filter(|o| o.cycle == current)
+            .map(|o| o.pc)
+            .collect();
+        let rows = self.sorted(history).into_iter().map(|b| {
+            let pht = b
+                .pht_indices
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+            let aliases = b
+                .aliases
+                .iter()
+                .map(|&pc| format!("{pc:x}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let row = Row::new(vec![
+                VerilogValue::from_usize(b.pc, usize::BITS as usize).as_addr(&snapshots.context),
+                b.executed.to_string(),
+                percent(b.taken, b.executed),
+                b.mispredicted.to_string(),
+                percent(b.executed - b.mispredicted, b.executed),
+                percent(b.btb.0, b.btb.1),
+                pht,
+                aliases,
+            ]);
+            if resolving.contains(&b.pc) {
+                row.yellow()
+            } else {
+                row
+            }
+        });
+
+        let title = Line::from("Branch Predictor").bold().centered();
+        let hint = Line::from(format!(
+            " <J/K> select, <S> sort (by {}), <n/N> next/previous mispredict ",
+            self.sort_by.name()
+        ))
+        .centered();
+        let block = Block::bordered().title(title).title_bottom(hint);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [summary_area, table_area] = Layout::vertical([
+            Constraint::Length(summary.len() as u16 + 1),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+        Paragraph::new(summary).render(summary_area, buf);
+
+        let table = Table::new(rows, WIDTHS)
+            .header(Row::new(HEADERS).bold().on_blue())
+            .row_highlight_style(Style::new().reversed());
+        let mut state = TableState::new().with_selected(self.selected);
+        StatefulWidget::render(table, table_area, buf, &mut state);
+    }
+}
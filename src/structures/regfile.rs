@@ -10,6 +10,8 @@ use ratatui::{
 
 use crate::snapshots::Snapshots;
 
+use super::view::impl_structure_view;
+
 const HEADERS: [(&str, bool); 2] = [("phys_reg", false), ("data", false)];
 const WIDTHS: [u16; 2] = [8, 16];
 
@@ -37,7 +39,7 @@ impl RegFile {
         })
     }
 
-    fn get_rows(&self, snapshots: &Snapshots, range: Range<usize>) -> Vec<Row> {
+    fn get_rows(&self, snapshots: &Snapshots, range: Range<usize>) -> Vec<Row<'_>> {
         let mut rows = Vec::new();
 
         for i in range {
@@ -50,8 +52,7 @@ impl RegFile {
                     let string = match *name {
                         "phys_reg" => i.to_string(),
                         "data" => snapshots
-                            .get_var(&format!("{}.regfile_mem.memData[{i}]", self.base))
-                            .unwrap()
+                            .get_var_or_missing(&format!("{}.regfile_mem.memData[{i}]", self.base))
                             .as_hex(),
                         _ => unreachable!(),
                     };
@@ -92,3 +93,5 @@ impl StatefulWidget for RegFile {
         Widget::render(second_table, right_area, buf);
     }
 }
+
+impl_structure_view!(RegFile, "Register File");
@@ -0,0 +1,121 @@
+use std::{cell::OnceCell, rc::Rc};
+
+use crossterm::event::KeyEvent;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Row, Table, Widget},
+};
+
+use crate::{retire::RetireLog, snapshots::Snapshots, utils::Scroll};
+
+use super::{Discovery, StructureView};
+
+const HEADERS: [&str; 5] = ["cycle", "PC", "inst", "rd", "value"];
+const WIDTHS: [Constraint; 5] = [
+    Constraint::Length(8),
+    Constraint::Length(8),
+    Constraint::Fill(1),
+    Constraint::Length(3),
+    Constraint::Length(10),
+];
+
+/// Every committed instruction, scrolled to whatever retired at the cursor.
+pub struct RetireLogView {
+    base: String,
+    /// extracted the first time it's shown, since it has to go through the whole trace
+    log: Rc<OnceCell<Option<RetireLog>>>,
+    /// rows scrolled away from the cursor with <J/K>
+    scroll: Scroll,
+}
+
+impl RetireLogView {
+    pub fn new(
+        base: &str,
+        log: Rc<OnceCell<Option<RetireLog>>>,
+        snapshots: &Snapshots,
+    ) -> Option<Self> {
+        if !RetireLog::available(base, snapshots) {
+            return None;
+        }
+
+        Some(Self {
+            base: base.to_owned(),
+            log,
+            scroll: Scroll::default(),
+        })
+    }
+}
+
+impl StructureView for RetireLogView {
+    /// The first core's log is shared with the other views that need it.
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        let log = if base == discovery.first_core() {
+            discovery.retire_log.clone()
+        } else {
+            Rc::default()
+        };
+        Self::new(base, log, discovery.snapshots)
+    }
+
+    fn name(&self) -> String {
+        String::from("Retired")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        self.scroll.on_key_event(key)
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let empty = RetireLog {
+            entries: Vec::new(),
+        };
+        let log = self
+            .log
+            .get_or_init(|| RetireLog::extract_at(&self.base, snapshots))
+            .as_ref()
+            .unwrap_or(&empty);
+
+        let current = snapshots.cycle();
+        // everything that retired up to and including the cursor
+        let retired = log.entries.partition_point(|entry| entry.cycle <= current);
+
+        let num_rows = area.height.saturating_sub(3) as usize;
+        // keep the last retired instruction a few rows from the bottom
+        let synced = retired.saturating_sub(num_rows.saturating_sub(4));
+        let first = self.scroll.first(synced, log.entries.len(), num_rows);
+
+        let rows = log.entries.iter().skip(first).take(num_rows).map(|entry| {
+            let row = Row::new(vec![
+                entry.clock.to_string(),
+                format!("{:x}", entry.pc),
+                entry.disasm.clone(),
+                entry.rd.map_or(String::from("-"), |rd| format!("x{rd}")),
+                entry
+                    .rd
+                    .map_or(String::from("-"), |_| format!("{:#x}", entry.value)),
+            ]);
+            if entry.cycle == current {
+                row.on_green()
+            } else if entry.cycle > current {
+                row.dim()
+            } else {
+                row
+            }
+        });
+
+        let title = Line::from(format!("Retired ({}/{})", retired, log.entries.len()))
+            .bold()
+            .centered();
+        Table::new(rows, WIDTHS)
+            .header(Row::new(HEADERS).bold().on_blue())
+            .block(Block::bordered().title(title))
+            .render(area, buf);
+    }
+}
@@ -1,5 +1,6 @@
 use std::cmp::max;
 
+use crossterm::event::KeyEvent;
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
@@ -10,6 +11,8 @@ use ratatui::{
 
 use crate::{snapshots::Snapshots, utils::parse_opinfo};
 
+use super::{select_row, view::impl_structure_view};
+
 // true if we can use the raw name as the key to index
 const HEADERS: [(&str, bool); 7] = [
     ("#", false),
@@ -25,6 +28,7 @@ const HEADERS: [(&str, bool); 7] = [
 pub struct ROBTable {
     base: String,
     size: usize,
+    selected: Option<usize>,
 }
 
 impl ROBTable {
@@ -43,6 +47,7 @@ impl ROBTable {
         Some(Self {
             base: base.to_owned(),
             size: i,
+            selected: None,
         })
     }
 }
@@ -57,16 +62,13 @@ impl StatefulWidget for ROBTable {
         let mut rows = Vec::new();
 
         let head_index = snapshots
-            .get_var(&format!("{}.head", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.head", self.base))
             .as_usize();
         let tail_index = snapshots
-            .get_var(&format!("{}.tail", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.tail", self.base))
             .as_usize();
         let rob_size = snapshots
-            .get_var(&format!("{}.size", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.size", self.base))
             .as_usize();
 
         for i in 0..self.size {
@@ -91,7 +93,7 @@ impl StatefulWidget for ROBTable {
 
                 let string = if *is_key {
                     let full_key = format!("{row_base}.{name}");
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
 
                     match *name {
                         "t" | "t_old" => value.as_decimal(),
@@ -133,6 +135,10 @@ impl StatefulWidget for ROBTable {
                 row = row.dim()
             }
 
+            if self.selected == Some(i) {
+                row = row.reversed();
+            }
+
             rows.push(row)
         }
 
@@ -142,3 +148,15 @@ impl StatefulWidget for ROBTable {
         Widget::render(table, area, buf);
     }
 }
+
+impl_structure_view!(ROBTable, "Reorder Buffer", {
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        select_row(&mut self.selected, self.size, key)
+    }
+
+    fn selected_inst(&self, snapshots: &Snapshots) -> Option<(usize, usize)> {
+        let i = self.selected?;
+        let pc = snapshots.get_var(&format!("{}.entries[{i}].info.PC", self.base))?;
+        Some((i, pc.as_usize()))
+    }
+});
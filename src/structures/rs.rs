@@ -1,3 +1,4 @@
+use crossterm::event::KeyEvent;
 use ratatui::{
     style::Stylize,
     text::Line,
@@ -9,6 +10,8 @@ use crate::{
     utils::{parse_fu_type, parse_opinfo},
 };
 
+use super::{select_row, view::impl_structure_view};
+
 // true if we can use the raw name as the key to index
 const HEADERS: [(&str, bool); 10] = [
     ("#", false),
@@ -28,6 +31,7 @@ const WIDTHS: [u16; 10] = [2, 8, 7, 7, 7, 6, 7, 6, 10, 20];
 pub struct RSTable {
     base: String,
     size: usize,
+    selected: Option<usize>,
 }
 
 impl RSTable {
@@ -46,6 +50,7 @@ impl RSTable {
         Some(Self {
             base: base.to_owned(),
             size: i,
+            selected: None,
         })
     }
 }
@@ -71,7 +76,7 @@ impl StatefulWidget for RSTable {
             for (name, is_key) in HEADERS.iter() {
                 let string = if *is_key {
                     let full_key = format!("{row_base}.{name}");
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
 
                     if *name == "fu" && (value.is_low() || value.is_unknown()) {
                         is_valid = false
@@ -108,7 +113,7 @@ impl StatefulWidget for RSTable {
                     parse_opinfo(&opinfo_base, snapshots)
                 } else if *name == "sq_tag" {
                     let full_key = format!("{row_base}.store_queue_tag");
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
                     value.as_decimal()
                 } else {
                     unreachable!()
@@ -138,6 +143,10 @@ impl StatefulWidget for RSTable {
                 row = row.dim();
             }
 
+            if self.selected == Some(i) {
+                row = row.reversed();
+            }
+
             rows.push(row)
         }
 
@@ -147,3 +156,22 @@ impl StatefulWidget for RSTable {
         Widget::render(table, area, buf);
     }
 }
+
+impl_structure_view!(RSTable, "Reservation Station", {
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        select_row(&mut self.selected, self.size, key)
+    }
+
+    fn selected_inst(&self, snapshots: &Snapshots) -> Option<(usize, usize)> {
+        let row_base = format!("{}.entries[{}]", self.base, self.selected?);
+
+        let fu = snapshots.get_var(&format!("{row_base}.fu"))?;
+        if fu.is_low() || fu.is_unknown() {
+            return None;
+        }
+
+        let rob_num = snapshots.get_var(&format!("{row_base}.rob_num"))?;
+        let pc = snapshots.get_var(&format!("{row_base}.op.PC"))?;
+        Some((rob_num.as_usize(), pc.as_usize()))
+    }
+});
@@ -0,0 +1,163 @@
+use std::{cell::OnceCell, rc::Rc};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Bar, BarChart, BarGroup, Block, Row, Table, Widget},
+};
+
+use crate::{
+    perf::{PerfTrace, Stall},
+    retire::RetireLog,
+    snapshots::Snapshots,
+};
+
+use super::{perf::collect_trace, view::select_row, Discovery, StructureView};
+
+const HEADERS: [&str; 4] = ["cause", "cycles", "share", "longest (cycles)"];
+const WIDTHS: [Constraint; 4] = [
+    Constraint::Length(16),
+    Constraint::Length(7),
+    Constraint::Length(6),
+    Constraint::Fill(1),
+];
+
+/// Why the core didn't retire anything, cycle by cycle, with a way to jump to the worst streak of
+/// each cause.
+pub struct Stalls {
+    base: String,
+    /// shared with the "Perf" tab, collected the first time either is shown
+    trace: Rc<OnceCell<PerfTrace>>,
+    /// what the core retired, which the counters are collected from
+    retire_log: Rc<OnceCell<Option<RetireLog>>>,
+    /// row of [`Stall::ALL`] selected with <J/K>
+    selected: Option<usize>,
+    jump: Option<usize>,
+}
+
+impl Stalls {
+    pub fn new(
+        base: &str,
+        trace: Rc<OnceCell<PerfTrace>>,
+        retire_log: Rc<OnceCell<Option<RetireLog>>>,
+    ) -> Self {
+        Self {
+            base: base.to_owned(),
+            trace,
+            retire_log,
+            selected: None,
+            jump: None,
+        }
+    }
+}
+
+impl StructureView for Stalls {
+    /// Stalls are told apart by what's at the head of the ROB, so `base` has to be one.
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        discovery
+            .snapshots
+            .get_var(&format!("{base}.dbg_this_is_rob"))?;
+        Some(Self::new(
+            base,
+            discovery.perf_trace.clone(),
+            discovery.retire_log.clone(),
+        ))
+    }
+
+    fn name(&self) -> String {
+        String::from("Stalls")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        if key.code == KeyCode::Enter {
+            let stall = Stall::ALL[self.selected.unwrap_or(0)];
+            self.jump = self
+                .trace
+                .get()
+                .and_then(|trace| trace.longest_stall(stall))
+                .map(|streak| streak.start);
+            return true;
+        }
+        select_row(&mut self.selected, Stall::ALL.len(), key)
+    }
+
+    fn take_jump(&mut self) -> Option<usize> {
+        self.jump.take()
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let trace = collect_trace(&self.trace, &self.retire_log, &self.base, snapshots);
+        let stalls = trace.stalls();
+        let total: usize = stalls.iter().sum();
+        let current = trace.samples.get(snapshots.cycle()).and_then(|s| s.stall);
+
+        let rows = Stall::ALL
+            .iter()
+            .zip(stalls)
+            .enumerate()
+            .map(|(i, (&stall, cycles))| {
+                let share = format!("{:.0}%", 100.0 * cycles as f64 / total.max(1) as f64);
+                let longest = trace.longest_stall(stall).map_or(String::new(), |streak| {
+                    format!("{} from clock {}", streak.len(), trace.clocks[streak.start])
+                });
+                let mut row = Row::new(vec![
+                    stall.name().to_owned(),
+                    cycles.to_string(),
+                    share,
+                    longest,
+                ]);
+                if current == Some(stall) {
+                    row = row.yellow();
+                }
+                if self.selected == Some(i) {
+                    row = row.reversed();
+                }
+                row
+            });
+
+        let title = Line::from(format!(
+            "Stalls ({total} of {} cycles retired nothing)",
+            trace.samples.len()
+        ))
+        .bold()
+        .centered();
+        let hint = Line::from(" <J/K> select, <Enter> jump to longest streak ").centered();
+        let block = Block::bordered().title(title).title_bottom(hint);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [table_area, chart_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Fill(1)]).areas(inner);
+        Table::new(rows, WIDTHS)
+            .header(Row::new(HEADERS).bold().on_blue())
+            .render(table_area, buf);
+
+        let bars: Vec<Bar> = Stall::ALL
+            .iter()
+            .zip(stalls)
+            .map(|(stall, cycles)| {
+                Bar::default()
+                    .value(cycles as u64)
+                    .label(Line::from(stall.name()))
+                    .style(if current == Some(*stall) {
+                        Style::new().yellow()
+                    } else {
+                        Style::new().cyan()
+                    })
+            })
+            .collect();
+        BarChart::default()
+            .direction(Direction::Horizontal)
+            .bar_width(1)
+            .bar_gap(0)
+            .data(BarGroup::default().bars(&bars))
+            .render(chart_area, buf);
+    }
+}
@@ -10,6 +10,8 @@ use ratatui::{
 
 use crate::snapshots::Snapshots;
 
+use super::view::impl_structure_view;
+
 // true if we can use the raw name as the key to index
 const HEADERS: [(&str, bool); 8] = [
     ("#", false),
@@ -64,16 +66,13 @@ impl StatefulWidget for StoreQueue {
         let mut rows = Vec::new();
 
         let head_index = snapshots
-            .get_var(&format!("{}.head", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.head", self.base))
             .as_usize();
         let tail_index = snapshots
-            .get_var(&format!("{}.tail", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.tail", self.base))
             .as_usize();
         let rob_size = snapshots
-            .get_var(&format!("{}.size", self.base))
-            .unwrap()
+            .get_var_or_missing(&format!("{}.size", self.base))
             .as_usize();
 
         for i in 0..self.size {
@@ -98,11 +97,12 @@ impl StatefulWidget for StoreQueue {
 
                 let string = if *is_key {
                     let full_key = format!("{row_base}.{name}");
-                    let value = snapshots.get_var(&full_key).unwrap();
+                    let value = snapshots.get_var_or_missing(&full_key);
 
                     match *name {
                         "rob_num" => value.as_decimal(),
-                        "data" | "addr" => value.as_hex(),
+                        "data" => value.as_hex(),
+                        "addr" => value.as_addr(&snapshots.context),
                         _ => {
                             format!("{}", value)
                         }
@@ -147,3 +147,5 @@ impl StatefulWidget for StoreQueue {
         Widget::render(table, area, buf);
     }
 }
+
+impl_structure_view!(StoreQueue, "Store Queue");
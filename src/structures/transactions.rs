@@ -0,0 +1,324 @@
+use std::cell::{Cell, OnceCell};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{
+        Bar, BarChart, BarGroup, Block, Paragraph, Row, StatefulWidget, Table, TableState, Widget,
+    },
+};
+
+use crate::{
+    snapshots::Snapshots,
+    transactions::{MemTraffic, Transaction},
+};
+
+use super::{view::select_row, Discovery, StructureView, CACHES};
+
+const HEADERS: [&str; 6] = ["tag", "addr", "from", "sent", "answered", "latency"];
+const WIDTHS: [Constraint; 6] = [
+    Constraint::Length(4),
+    Constraint::Length(10),
+    Constraint::Fill(1),
+    Constraint::Length(8),
+    Constraint::Length(8),
+    Constraint::Length(10),
+];
+/// Most bars in the latency histogram; wider latency ranges are bucketed.
+const MAX_BARS: usize = 16;
+const MAX_BAR_WIDTH: u16 = 6;
+
+/// Where a row of the list came from.
+enum Entry<'a> {
+    Load(&'a Transaction),
+    /// cycle, clock count and tag of a response nobody asked for
+    Unmatched(usize, usize, usize),
+}
+
+impl Entry<'_> {
+    /// Cycle to jump to for the row.
+    fn cycle(&self) -> usize {
+        match self {
+            Entry::Load(transaction) => transaction.issue_cycle,
+            Entry::Unmatched(cycle, _, _) => *cycle,
+        }
+    }
+}
+
+/// Loads on the memory bus and from memory units to their cache, with how long they took to be
+/// answered.
+pub struct Transactions {
+    base: String,
+    /// caches whose MSHRs loads are put down to
+    caches: Vec<String>,
+    /// memory units whose cache queries are followed
+    memunits: Vec<String>,
+    /// extracted the first time it's shown, since it has to go through the whole trace
+    traffic: OnceCell<MemTraffic>,
+    /// whether to only list loads outstanding at the cursor, toggled with <O>
+    outstanding_only: bool,
+    /// row selected with <J/K>
+    selected: Option<usize>,
+    /// cycle the view was last shown at, for what's outstanding
+    cursor: Cell<usize>,
+    jump: Option<usize>,
+}
+
+impl Transactions {
+    /// `base` is the testbench, where the memory bus is.
+    pub fn new(
+        base: &str,
+        caches: Vec<String>,
+        memunits: Vec<String>,
+        snapshots: &Snapshots,
+    ) -> Option<Self> {
+        let has_bus = snapshots
+            .get_var(&format!("{base}.proc2mem_command"))
+            .and(snapshots.get_var(&format!("{base}.mem2proc_data_tag")))
+            .is_some();
+        if !has_bus && memunits.is_empty() {
+            return None;
+        }
+        Some(Self {
+            base: base.to_owned(),
+            caches,
+            memunits,
+            traffic: OnceCell::new(),
+            outstanding_only: false,
+            selected: None,
+            cursor: Cell::new(0),
+            jump: None,
+        })
+    }
+
+    /// Rows of the list, in the order things happened.
+    fn entries<'a>(&self, traffic: &'a MemTraffic, cycle: usize) -> Vec<Entry<'a>> {
+        let mut entries: Vec<Entry> = traffic
+            .transactions
+            .iter()
+            .filter(|transaction| !self.outstanding_only || transaction.outstanding_at(cycle))
+            .map(Entry::Load)
+            .collect();
+        if !self.outstanding_only {
+            entries.extend(
+                traffic
+                    .unmatched
+                    .iter()
+                    .map(|response| Entry::Unmatched(response.cycle, response.clock, response.tag)),
+            );
+            entries.sort_by_key(Entry::cycle);
+        }
+        entries
+    }
+
+    fn render_histogram(&self, traffic: &MemTraffic, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered().title(Line::from("Latency (clocks)").bold());
+        let (Some(min), Some(max)) = (traffic.latencies().min(), traffic.latencies().max()) else {
+            Paragraph::new("No loads were answered")
+                .block(block)
+                .render(area, buf);
+            return;
+        };
+        let width = (max - min + 1).div_ceil(MAX_BARS);
+        let mut counts = vec![0u64; (max - min) / width + 1];
+        for latency in traffic.latencies() {
+            counts[(latency - min) / width] += 1;
+        }
+
+        let bars: Vec<Bar> = counts
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let low = min + i * width;
+                let label = if width == 1 {
+                    low.to_string()
+                } else {
+                    format!("{low}-{}", low + width - 1)
+                };
+                Bar::default()
+                    .value(count)
+                    .label(Line::from(label))
+                    .style(Style::new().cyan())
+            })
+            .collect();
+        let bar_width = (area.width.saturating_sub(2) / counts.len().max(1) as u16)
+            .saturating_sub(1)
+            .clamp(1, MAX_BAR_WIDTH);
+        BarChart::default()
+            .block(block)
+            .bar_width(bar_width)
+            .data(BarGroup::default().bars(&bars))
+            .render(area, buf);
+    }
+}
+
+impl StructureView for Transactions {
+    /// Loads are put down to whichever cache in the design has an MSHR waiting on them, and every
+    /// memory unit's cache queries are followed too.
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self> {
+        Self::new(
+            base,
+            discovery.bases(CACHES),
+            discovery.bases(&["memunit"]),
+            discovery.snapshots,
+        )
+    }
+
+    fn name(&self) -> String {
+        String::from("Transactions")
+    }
+
+    fn base(&self) -> &str {
+        &self.base
+    }
+
+    fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        let Some(traffic) = self.traffic.get() else {
+            return false;
+        };
+        let entries = self.entries(traffic, self.cursor.get());
+        if select_row(&mut self.selected, entries.len(), key) {
+            return true;
+        }
+        match key.code {
+            KeyCode::Char('O') => {
+                self.outstanding_only = !self.outstanding_only;
+                self.selected = None;
+            }
+            KeyCode::Enter => {
+                self.jump = self.selected.and_then(|i| entries.get(i)).map(Entry::cycle);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn take_jump(&mut self) -> Option<usize> {
+        self.jump.take()
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots) {
+        let traffic = self.traffic.get_or_init(|| {
+            MemTraffic::extract(&self.base, &self.caches, &self.memunits, snapshots)
+        });
+        let current = snapshots.cycle();
+        self.cursor.set(current);
+
+        let loads = traffic.transactions.len();
+        let answered = traffic.latencies().count();
+        let outstanding = traffic
+            .transactions
+            .iter()
+            .filter(|transaction| transaction.outstanding_at(current))
+            .count();
+        let mut summary = vec![Line::from(format!(
+            "{loads} loads, {answered} answered, {outstanding} outstanding at the cursor"
+        ))];
+        if answered > 0 {
+            let total: usize = traffic.latencies().sum();
+            summary.push(Line::from(format!(
+                "latency: {} min, {:.1} mean, {} max clocks",
+                traffic.latencies().min().unwrap_or(0),
+                total as f64 / answered as f64,
+                traffic.latencies().max().unwrap_or(0),
+            )));
+        }
+        if !traffic.unmatched.is_empty() {
+            summary.push(
+                Line::from(format!(
+                    "{} responses with no matching request",
+                    traffic.unmatched.len()
+                ))
+                .red(),
+            );
+        }
+
+        let entries = self.entries(traffic, current);
+        let rows = entries.iter().map(|entry| match entry {
+            Entry::Load(transaction) => {
+                let source = transaction
+                    .source
+                    .as_ref()
+                    .and_then(|cache| cache.rsplit('.').next())
+                    .unwrap_or("-");
+                let (answered, latency) = match transaction.response {
+                    Some((_, clock)) => (
+                        clock.to_string(),
+                        transaction.latency().unwrap_or(0).to_string(),
+                    ),
+                    None => (String::from("-"), String::from("-")),
+                };
+                let row = Row::new(vec![
+                    transaction
+                        .tag
+                        .map_or(String::from("-"), |tag| tag.to_string()),
+                    format!("{:#x}", transaction.addr),
+                    source.to_owned(),
+                    transaction.issue_clock.to_string(),
+                    answered,
+                    latency,
+                ]);
+                if transaction.outstanding_at(current) {
+                    row.yellow()
+                } else {
+                    row
+                }
+            }
+            Entry::Unmatched(_, clock, tag) => Row::new(vec![
+                tag.to_string(),
+                String::from("-"),
+                String::from("-"),
+                String::from("-"),
+                clock.to_string(),
+                String::from("no request"),
+            ])
+            .red(),
+        });
+
+        let title = Line::from("Memory Transactions").bold().centered();
+        let shown = if self.outstanding_only {
+            "outstanding"
+        } else {
+            "all"
+        };
+        let hint = Line::from(format!(
+            " <J/K> select, <Enter> jump to it, <O> show outstanding/all (showing {shown}) "
+        ))
+        .centered();
+        let block = Block::bordered().title(title).title_bottom(hint);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let [summary_area, main_area] = Layout::vertical([
+            Constraint::Length(summary.len() as u16 + 1),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+        Paragraph::new(summary).render(summary_area, buf);
+        let [table_area, histogram_area] =
+            Layout::horizontal([Constraint::Fill(3), Constraint::Fill(2)]).areas(main_area);
+
+        // without a selection, scroll past what was over before the cursor
+        let offset = entries
+            .iter()
+            .position(|entry| match entry {
+                Entry::Load(transaction) => transaction
+                    .response
+                    .is_none_or(|(response, _)| response >= current),
+                Entry::Unmatched(cycle, _, _) => *cycle >= current,
+            })
+            .unwrap_or(0);
+        let table = Table::new(rows, WIDTHS)
+            .header(Row::new(HEADERS).bold().on_blue())
+            .row_highlight_style(Style::new().reversed());
+        let mut state = TableState::new()
+            .with_offset(offset)
+            .with_selected(self.selected);
+        StatefulWidget::render(table, table_area, buf, &mut state);
+
+        self.render_histogram(traffic, histogram_area, buf);
+    }
+}
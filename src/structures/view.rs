@@ -0,0 +1,206 @@
+use std::{cell::OnceCell, rc::Rc};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{buffer::Buffer, layout::Rect};
+
+use crate::{perf::PerfTrace, program::MemoryImage, retire::RetireLog, snapshots::Snapshots};
+
+/// A table/panel for one module of the design. Adding a new view means implementing this in its
+/// own file (usually with [`impl_structure_view`]) and adding a [`ViewKind`] for it to `REGISTRY`
+/// in `structures/mod.rs`, which says what modules it's built for and which tab it goes on.
+pub trait StructureView {
+    /// Builds the view for the module at `base`, or `None` if `base` isn't that kind of module.
+    fn discover(base: &str, discovery: &Discovery) -> Option<Self>
+    where
+        Self: Sized;
+
+    /// Short name used for tab titles, e.g. "ROB".
+    fn name(&self) -> String;
+
+    /// Hierarchical path of the module this view reads from.
+    fn base(&self) -> &str;
+
+    fn render(&self, area: Rect, buf: &mut Buffer, snapshots: &mut Snapshots);
+
+    /// Returns true if the view used the key, so it isn't handled by anything else.
+    fn on_key_event(&mut self, _key: KeyEvent) -> bool {
+        false
+    }
+
+    /// ROB number and PC of the instruction in the selected row, for views with selectable rows.
+    fn selected_inst(&self, _snapshots: &Snapshots) -> Option<(usize, usize)> {
+        None
+    }
+
+    /// Cycle (counted from the start of the trace) the last key asked to move the cursor to, if
+    /// any. Taking it clears it.
+    fn take_jump(&mut self) -> Option<usize> {
+        None
+    }
+
+    /// Height the view wants when it's stacked on top of others, if it needs a fixed one.
+    fn height_hint(&self) -> Option<u16> {
+        None
+    }
+}
+
+/// Implements [`StructureView`] for a view that's also a [`ratatui::widgets::StatefulWidget`]
+/// over [`Snapshots`], with a `base` field and a `new(base, snapshots) -> Option<Self>`
+/// constructor. Any other methods of the trait go in the braces.
+macro_rules! impl_structure_view {
+    ($view:ty, $name:literal $(, { $($methods:tt)* })?) => {
+        impl $crate::structures::StructureView for $view {
+            fn discover(base: &str, discovery: &$crate::structures::Discovery) -> Option<Self> {
+                Self::new(base, discovery.snapshots)
+            }
+
+            fn name(&self) -> String {
+                String::from($name)
+            }
+
+            fn base(&self) -> &str {
+                &self.base
+            }
+
+            fn render(
+                &self,
+                area: ::ratatui::layout::Rect,
+                buf: &mut ::ratatui::buffer::Buffer,
+                snapshots: &mut $crate::snapshots::Snapshots,
+            ) {
+                ::ratatui::widgets::StatefulWidget::render(self.clone(), area, buf, snapshots);
+            }
+
+            $($($methods)*)?
+        }
+    };
+}
+pub(crate) use impl_structure_view;
+
+/// Everything views are discovered from, and what they share so it's only extracted once.
+pub struct Discovery<'a> {
+    pub snapshots: &'a Snapshots,
+    /// what the testbench loaded, if given
+    pub program: Option<&'a MemoryImage>,
+    /// every (marker, path) in the design, see `find_markers`
+    pub markers: Vec<(String, String)>,
+    /// per-cycle samples of the first core, for the views that chart them
+    pub perf_trace: Rc<OnceCell<PerfTrace>>,
+    /// what the first core retired, see [`RetireLog::extract`]
+    pub retire_log: Rc<OnceCell<Option<RetireLog>>>,
+}
+
+impl Discovery<'_> {
+    /// Paths of every module with one of `markers`, in the order they're in the design.
+    pub fn bases(&self, markers: &[&str]) -> Vec<String> {
+        self.markers
+            .iter()
+            .filter(|(marker, _)| markers.contains(&marker.as_str()))
+            .map(|(_, base)| base.clone())
+            .collect()
+    }
+
+    /// Whether any module has one of `markers`.
+    pub fn has(&self, markers: &[&str]) -> bool {
+        self.markers
+            .iter()
+            .any(|(marker, _)| markers.contains(&marker.as_str()))
+    }
+
+    /// Paths of every ROB, or the testbench if there aren't any.
+    fn cores(&self) -> Vec<String> {
+        let robs = self.bases(&["rob"]);
+        if robs.is_empty() {
+            vec![self.snapshots.get_base()]
+        } else {
+            robs
+        }
+    }
+
+    /// Path of the first ROB, or the testbench if there aren't any.
+    pub fn first_core(&self) -> String {
+        self.cores().swap_remove(0)
+    }
+
+    /// Paths of the modules `kind` is built for.
+    pub fn sources(&self, kind: &ViewKind) -> Vec<String> {
+        match kind.source {
+            Source::Marker => self.bases(&[kind.marker]),
+            Source::Each(markers) => self.bases(markers),
+            Source::Cores => self.cores(),
+            Source::FirstCore => vec![self.first_core()],
+            Source::Testbench => vec![self.snapshots.get_base()],
+        }
+    }
+}
+
+/// Which modules a kind of view is built for.
+#[derive(Clone, Copy)]
+pub enum Source {
+    /// every module with the kind's own `dbg_this_is_<marker>`
+    Marker,
+    /// every module with one of these markers, e.g. a pipeline diagram per ROB
+    Each(&'static [&'static str]),
+    /// every ROB, or the testbench if there aren't any, for views of a whole core
+    Cores,
+    /// the first ROB, or the testbench if there aren't any
+    FirstCore,
+    /// the testbench, where the memory bus is
+    Testbench,
+}
+
+/// Registry entry tying a kind of view to the modules it's built for.
+pub struct ViewKind {
+    /// `dbg_this_is_<marker>` of the modules it displays, or for views found some other way, a
+    /// name telling its instances apart from other views'
+    pub marker: &'static str,
+    pub source: Source,
+    /// tab it shares with other views in cpu mode; views without one get a tab of their own
+    pub tab: Option<&'static str>,
+    pub discover: fn(&str, &Discovery) -> Option<Box<dyn StructureView>>,
+}
+
+impl ViewKind {
+    pub const fn of<T: StructureView + 'static>(marker: &'static str) -> Self {
+        Self {
+            marker,
+            source: Source::Marker,
+            tab: None,
+            discover: discover_boxed::<T>,
+        }
+    }
+
+    pub const fn from(self, source: Source) -> Self {
+        Self { source, ..self }
+    }
+
+    pub const fn on_tab(self, tab: &'static str) -> Self {
+        Self {
+            tab: Some(tab),
+            ..self
+        }
+    }
+}
+
+/// Type-erased [`StructureView::discover`], for use in [`ViewKind`].
+pub fn discover_boxed<T: StructureView + 'static>(
+    base: &str,
+    discovery: &Discovery,
+) -> Option<Box<dyn StructureView>> {
+    T::discover(base, discovery).map(|view| Box::new(view) as Box<dyn StructureView>)
+}
+
+/// Moves `selected` through `size` rows with <J/K>. Returns true if the key was one of them.
+pub fn select_row(selected: &mut Option<usize>, size: usize, key: KeyEvent) -> bool {
+    if size == 0 {
+        return false;
+    }
+    *selected = match (key.code, *selected) {
+        (KeyCode::Char('J'), None) => Some(0),
+        (KeyCode::Char('J'), Some(i)) => Some((i + 1) % size),
+        (KeyCode::Char('K'), None) => Some(size - 1),
+        (KeyCode::Char('K'), Some(i)) => Some((i + size - 1) % size),
+        _ => return false,
+    };
+    true
+}
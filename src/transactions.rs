@@ -0,0 +1,179 @@
+//! Loads on the memory bus, matched with their responses by `mem_tag`, and loads memory units make
+//! to their cache, matched with the cache's next response.
+use crate::{
+    snapshots::{Snapshots, VerilogValue},
+    utils::{get_known, indexed},
+};
+
+/// `proc2mem_command`/`cache_query_command` value for a read, see `parse_mem_command`. Stores
+/// aren't answered, so they aren't transactions.
+const MEM_LOAD: usize = 0b01;
+
+/// A load sent to memory or a cache and when its data came back.
+#[derive(Clone, Debug)]
+pub struct Transaction {
+    /// what memory tagged the load with; cache queries don't have one
+    pub tag: Option<usize>,
+    pub addr: usize,
+    /// as a position in [`Snapshots::cycle_indices`]
+    pub issue_cycle: usize,
+    /// clock count of `issue_cycle`
+    pub issue_clock: usize,
+    /// (cycle, clock count) of the response, if it came before the trace ended
+    pub response: Option<(usize, usize)>,
+    /// cache with an MSHR waiting on the tag while the load was out, or the memory unit that
+    /// queried its cache
+    pub source: Option<String>,
+}
+
+impl Transaction {
+    /// Clock cycles from the request to the response.
+    pub fn latency(&self) -> Option<usize> {
+        self.response
+            .map(|(_, clock)| clock.saturating_sub(self.issue_clock))
+    }
+
+    /// Whether the load has been sent and not answered as of `cycle`.
+    pub fn outstanding_at(&self, cycle: usize) -> bool {
+        self.issue_cycle <= cycle && self.response.is_none_or(|(response, _)| cycle < response)
+    }
+}
+
+/// Data that came back with a tag that no load was waiting on.
+#[derive(Clone, Copy, Debug)]
+pub struct UnmatchedResponse {
+    /// as a position in [`Snapshots::cycle_indices`]
+    pub cycle: usize,
+    /// clock count of `cycle`
+    pub clock: usize,
+    pub tag: usize,
+}
+
+/// Every transaction on a memory bus over the trace.
+pub struct MemTraffic {
+    /// in the order they were sent
+    pub transactions: Vec<Transaction>,
+    /// in the order they came
+    pub unmatched: Vec<UnmatchedResponse>,
+}
+
+impl MemTraffic {
+    /// Goes through the whole trace watching the memory bus of the testbench at `base` and the
+    /// cache queries of `memunits`. Loads on the bus are put down to whichever of `caches` has a
+    /// valid MSHR with their tag.
+    pub fn extract(
+        base: &str,
+        caches: &[String],
+        memunits: &[String],
+        snapshots: &mut Snapshots,
+    ) -> Self {
+        let mshrs: Vec<(&String, Vec<String>)> = caches
+            .iter()
+            .map(|cache| {
+                (
+                    cache,
+                    indexed(&format!("{cache}.waiting_commands"), snapshots),
+                )
+            })
+            .collect();
+
+        let mut transactions: Vec<Transaction> = Vec::new();
+        let mut unmatched = Vec::new();
+        // indices into `transactions` of loads that haven't been answered
+        let mut outstanding: Vec<usize> = Vec::new();
+        // per memory unit, the (addr, index into `transactions`) of its unanswered cache query
+        let mut queries: Vec<Option<(usize, usize)>> = vec![None; memunits.len()];
+
+        let original = snapshots.index();
+        for (cycle, index) in snapshots.cycle_indices().into_iter().enumerate() {
+            snapshots.go_to(index);
+            let clock = snapshots.get_clock_count();
+
+            // a tag can be handed out again the cycle its response comes back, so responses go
+            // first
+            let response = get_known(&format!("{base}.mem2proc_data_tag"), snapshots);
+            if let Some(tag) = response.filter(|&tag| tag != 0) {
+                match outstanding
+                    .iter()
+                    .position(|&i| transactions[i].tag == Some(tag))
+                {
+                    Some(position) => {
+                        let i = outstanding.remove(position);
+                        transactions[i].response = Some((cycle, clock));
+                    }
+                    None => unmatched.push(UnmatchedResponse { cycle, clock, tag }),
+                }
+            }
+
+            let command = get_known(&format!("{base}.proc2mem_command"), snapshots);
+            let tag = get_known(&format!("{base}.mem2proc_transaction_tag"), snapshots);
+            // memory accepted the load if it gave it a tag
+            if let (Some(MEM_LOAD), Some(tag)) = (command, tag.filter(|&tag| tag != 0)) {
+                outstanding.push(transactions.len());
+                transactions.push(Transaction {
+                    tag: Some(tag),
+                    addr: get_known(&format!("{base}.proc2mem_addr"), snapshots).unwrap_or(0),
+                    issue_cycle: cycle,
+                    issue_clock: clock,
+                    response: None,
+                    source: None,
+                });
+            }
+
+            for &i in outstanding.iter() {
+                if transactions[i].source.is_some() {
+                    continue;
+                }
+                let tag = transactions[i].tag;
+                transactions[i].source = mshrs
+                    .iter()
+                    .find(|(_, entries)| {
+                        entries.iter().any(|mshr| {
+                            snapshots
+                                .get_var(&format!("{mshr}.valid"))
+                                .is_some_and(VerilogValue::is_high)
+                                && get_known(&format!("{mshr}.mem_tag"), snapshots) == tag
+                        })
+                    })
+                    .map(|(cache, _)| (*cache).clone());
+            }
+
+            // a memory unit holds its query until the cache answers, which can be the same cycle
+            for (memunit, query) in memunits.iter().zip(queries.iter_mut()) {
+                let command = get_known(&format!("{memunit}.cache_query_command"), snapshots);
+                let addr = get_known(&format!("{memunit}.cache_query_addr"), snapshots);
+                if let (Some(MEM_LOAD), Some(addr)) = (command, addr) {
+                    if query.is_none_or(|(pending, _)| pending != addr) {
+                        *query = Some((addr, transactions.len()));
+                        transactions.push(Transaction {
+                            tag: None,
+                            addr,
+                            issue_cycle: cycle,
+                            issue_clock: clock,
+                            response: None,
+                            source: Some(memunit.clone()),
+                        });
+                    }
+                }
+                let answered = snapshots
+                    .get_var(&format!("{memunit}.cache_resp_valid"))
+                    .is_some_and(VerilogValue::is_high);
+                if let Some((_, i)) = query.filter(|_| answered) {
+                    transactions[i].response = Some((cycle, clock));
+                    *query = None;
+                }
+            }
+        }
+        snapshots.go_to(original);
+
+        Self {
+            transactions,
+            unmatched,
+        }
+    }
+
+    /// Latencies of every answered load, in clock cycles.
+    pub fn latencies(&self) -> impl Iterator<Item = usize> + '_ {
+        self.transactions.iter().filter_map(Transaction::latency)
+    }
+}
@@ -5,6 +5,7 @@ use std::{
     io::{Error, ErrorKind, Result},
 };
 
+use crossterm::event::{KeyCode, KeyEvent};
 use raki::{
     AOpcode, BaseIOpcode, COpcode, Decode, InstFormat, Instruction, Isa, OpcodeKind, PrivOpcode,
     ZifenceiOpcode,
@@ -16,14 +17,17 @@ use ratatui::{
     widgets::{Cell, Row, Table},
 };
 
-use crate::snapshots::{Snapshots, VerilogValue};
+use crate::{
+    program::Symbols,
+    snapshots::{Snapshots, VerilogValue},
+};
 
 #[derive(Clone, Copy)]
 pub enum DisplayType {
     Binary,
     Decimal,
     Hex,
-    Custom(fn(&VerilogValue) -> String),
+    Custom(fn(&VerilogValue, &DisplayContext) -> String),
 }
 impl DisplayType {
     pub fn next(&self) -> Self {
@@ -107,23 +111,196 @@ pub fn load_watch_list(name: &str) -> Result<Vec<(String, DisplayType)>> {
     Ok(watch_list)
 }
 
+/// Which instructions get disassembled, e.g. `rv32imc`. Zicsr, Zifencei and the privileged
+/// instructions always are.
+#[derive(Clone, Copy)]
+pub struct IsaConfig {
+    pub xlen: Isa,
+    /// integer multiply and divide
+    pub m: bool,
+    /// atomics
+    pub a: bool,
+    /// 16-bit compressed instructions
+    pub c: bool,
+}
+
+impl Default for IsaConfig {
+    /// Everything raki decodes as 32-bit instructions, which is what was always used.
+    fn default() -> Self {
+        Self {
+            xlen: Isa::Rv32,
+            m: true,
+            a: true,
+            c: false,
+        }
+    }
+}
+
+impl IsaConfig {
+    /// Parses an ISA string such as `rv32i`, `rv32im` or `rv64imac`. Multi-letter extensions
+    /// after an underscore are ignored.
+    pub fn parse(isa: &str) -> Option<Self> {
+        let isa = isa.to_ascii_lowercase();
+        let (xlen, extensions) = if let Some(rest) = isa.strip_prefix("rv32") {
+            (Isa::Rv32, rest)
+        } else {
+            (Isa::Rv64, isa.strip_prefix("rv64")?)
+        };
+        let extensions = extensions.split('_').next()?.strip_prefix('i')?;
+
+        let mut config = Self {
+            xlen,
+            m: false,
+            a: false,
+            c: false,
+        };
+        for extension in extensions.chars() {
+            match extension {
+                'm' => config.m = true,
+                'a' => config.a = true,
+                'c' => config.c = true,
+                _ => return None,
+            }
+        }
+        Some(config)
+    }
+
+    /// Decodes instruction bits. Compressed instructions are in the low half.
+    pub fn decode(&self, inst_bits: usize) -> Option<Instruction> {
+        let inst = if inst_bits & 0b11 != 0b11 {
+            if !self.c {
+                return None;
+            }
+            (inst_bits as u16).decode(self.xlen).ok()?
+        } else {
+            (inst_bits as u32).decode(self.xlen).ok()?
+        };
+
+        let enabled = match inst.opc {
+            OpcodeKind::M(_) => self.m,
+            OpcodeKind::A(_) => self.a,
+            _ => true,
+        };
+        enabled.then_some(inst)
+    }
+
+    /// Size in bytes of the instruction starting with `inst_bits`.
+    pub fn inst_len(&self, inst_bits: usize) -> usize {
+        if self.c && inst_bits & 0b11 != 0b11 {
+            2
+        } else {
+            4
+        }
+    }
+}
+
+/// How instructions are decoded and values are shown: the ISA from `--isa`, the loaded
+/// program's symbols, and the register name and pseudo-instruction toggles.
+#[derive(Clone, Default)]
+pub struct DisplayContext {
+    pub isa: IsaConfig,
+    /// empty unless a program with a symbol table was loaded
+    pub symbols: Symbols,
+    /// show registers by their ABI names (`sp`, `a0`) instead of `x2`, `x10`
+    pub abi_registers: bool,
+    /// show instructions as the pseudo-instructions they're usually written as
+    pub pseudo_insts: bool,
+}
+
+impl DisplayContext {
+    /// `function+offset` or data symbol name of `addr` in the loaded program, if it has one.
+    pub fn symbolize(&self, addr: usize) -> Option<String> {
+        self.symbols.lookup(addr)
+    }
+
+    /// Disassembles raw instruction bits.
+    pub fn disasm(&self, inst_bits: usize) -> String {
+        match self.isa.decode(inst_bits) {
+            Some(inst) => o3oInst(inst, self).to_string(),
+            None => String::from("<invalid>"),
+        }
+    }
+
+    /// Register an instruction writes, if it writes one.
+    pub fn inst_rd(&self, inst_bits: usize) -> Option<usize> {
+        self.isa.decode(inst_bits)?.rd
+    }
+
+    /// Name of register `index`.
+    fn reg_name(&self, index: usize) -> String {
+        if self.abi_registers {
+            ABI_NAMES[index].to_owned()
+        } else {
+            format!("x{index}")
+        }
+    }
+}
+
 // new type to allow us to redefine display
 #[allow(non_camel_case_types)]
-pub struct o3oInst(pub Instruction);
+pub struct o3oInst<'a>(pub Instruction, pub &'a DisplayContext);
 
 // code copied from raki's impl
 // https://docs.rs/raki/latest/src/raki/instruction.rs.html#46
-impl Display for o3oInst {
+impl o3oInst<'_> {
+    /// The pseudo-instruction this is usually written as, e.g. `li a0, 1` for `addi a0, zero, 1`.
+    fn pseudo(&self) -> Option<String> {
+        let Instruction {
+            opc,
+            rd,
+            rs1,
+            rs2,
+            imm,
+            ..
+        } = &self.0;
+        let OpcodeKind::BaseI(opc) = opc else {
+            return None;
+        };
+        let reg = |r: Option<usize>| r.map(|r| self.1.reg_name(r)).unwrap_or_default();
+        let (rd_name, rs1_name, rs2_name) = (reg(*rd), reg(*rs1), reg(*rs2));
+        let imm = imm.unwrap_or(0);
+
+        Some(match (opc, *rd, *rs1, *rs2, imm) {
+            (BaseIOpcode::ADDI, Some(0), Some(0), _, 0) => String::from("nop"),
+            (BaseIOpcode::ADDI, _, Some(0), _, _) => format!("li {rd_name}, {imm}"),
+            (BaseIOpcode::ADDI, _, _, _, 0) => format!("mv {rd_name}, {rs1_name}"),
+            (BaseIOpcode::XORI, _, _, _, -1) => format!("not {rd_name}, {rs1_name}"),
+            (BaseIOpcode::SUB, _, Some(0), _, _) => format!("neg {rd_name}, {rs2_name}"),
+            (BaseIOpcode::SLTIU, _, _, _, 1) => format!("seqz {rd_name}, {rs1_name}"),
+            (BaseIOpcode::SLTU, _, Some(0), _, _) => format!("snez {rd_name}, {rs2_name}"),
+            (BaseIOpcode::BEQ, _, _, Some(0), _) => format!("beqz {rs1_name}, {imm}"),
+            (BaseIOpcode::BNE, _, _, Some(0), _) => format!("bnez {rs1_name}, {imm}"),
+            (BaseIOpcode::BLT, _, _, Some(0), _) => format!("bltz {rs1_name}, {imm}"),
+            (BaseIOpcode::BGE, _, _, Some(0), _) => format!("bgez {rs1_name}, {imm}"),
+            (BaseIOpcode::BLT, _, Some(0), _, _) => format!("bgtz {rs2_name}, {imm}"),
+            (BaseIOpcode::BGE, _, Some(0), _, _) => format!("blez {rs2_name}, {imm}"),
+            (BaseIOpcode::JAL, Some(0), _, _, _) => format!("j {imm:#x}"),
+            (BaseIOpcode::JAL, Some(1), _, _, _) => format!("jal {imm:#x}"),
+            (BaseIOpcode::JALR, Some(0), Some(1), _, 0) => String::from("ret"),
+            (BaseIOpcode::JALR, Some(0), _, _, 0) => format!("jr {rs1_name}"),
+            (BaseIOpcode::JALR, Some(1), _, _, 0) => format!("jalr {rs1_name}"),
+            _ => return None,
+        })
+    }
+}
+
+impl Display for o3oInst<'_> {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.1.pseudo_insts {
+            if let Some(pseudo) = self.pseudo() {
+                return write!(f, "{pseudo}");
+            }
+        }
+
         match self.0.inst_format {
             InstFormat::RFormat | InstFormat::MFormat => {
                 write!(
                     f,
                     "{} {}, {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rs1.unwrap()),
-                    reg2str(self.0.rs2.unwrap())
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rs1.unwrap()),
+                    self.1.reg_name(self.0.rs2.unwrap())
                 )
             }
             InstFormat::AFormat => match self.0.opc {
@@ -131,16 +308,16 @@ impl Display for o3oInst {
                     f,
                     "{} {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rs1.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rs1.unwrap()),
                 ),
                 _ => write!(
                     f,
                     "{} {}, {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rs1.unwrap()),
-                    reg2str(self.0.rs2.unwrap())
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rs1.unwrap()),
+                    self.1.reg_name(self.0.rs2.unwrap())
                 ),
             },
             InstFormat::RShamtFormat => {
@@ -148,58 +325,60 @@ impl Display for o3oInst {
                     f,
                     "{} {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rs1.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rs1.unwrap()),
                 )
             }
             InstFormat::ClFormat | InstFormat::ALrFormat | InstFormat::IFormat => write!(
                 f,
                 "{} {}, {}, {}",
                 self.0.opc,
-                reg2str(self.0.rd.unwrap()),
-                reg2str(self.0.rs1.unwrap()),
+                self.1.reg_name(self.0.rd.unwrap()),
+                self.1.reg_name(self.0.rs1.unwrap()),
                 self.0.imm.unwrap()
             ),
             InstFormat::SFormat => write!(
                 f,
                 "{} {}, {}({})",
                 self.0.opc,
-                reg2str(self.0.rs2.unwrap()),
+                self.1.reg_name(self.0.rs2.unwrap()),
                 self.0.imm.unwrap(),
-                reg2str(self.0.rs1.unwrap()),
+                self.1.reg_name(self.0.rs1.unwrap()),
             ),
             InstFormat::BFormat => write!(
                 f,
                 "{} {}, {}, {}",
                 self.0.opc,
-                reg2str(self.0.rs1.unwrap()),
-                reg2str(self.0.rs2.unwrap()),
+                self.1.reg_name(self.0.rs1.unwrap()),
+                self.1.reg_name(self.0.rs2.unwrap()),
                 self.0.imm.unwrap(),
             ),
             InstFormat::CsFormat => write!(
                 f,
                 "{} {}, {}({})",
                 self.0.opc,
-                reg2str(self.0.rs1.unwrap()),
+                self.1.reg_name(self.0.rs1.unwrap()),
                 self.0.imm.unwrap(),
-                reg2str(self.0.rs2.unwrap()),
+                self.1.reg_name(self.0.rs2.unwrap()),
             ),
             InstFormat::CiwFormat => {
                 write!(
                     f,
-                    "{} {}, sp, {:x}",
+                    "{} {}, {}, {:x}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(2),
                     self.0.imm.unwrap()
                 )
             }
             InstFormat::CssFormat => {
                 write!(
                     f,
-                    "{} {}, {}(sp)",
+                    "{} {}, {}({})",
                     self.0.opc,
-                    reg2str(self.0.rs2.unwrap()),
-                    self.0.imm.unwrap()
+                    self.1.reg_name(self.0.rs2.unwrap()),
+                    self.0.imm.unwrap(),
+                    self.1.reg_name(2)
                 )
             }
             InstFormat::UFormat | InstFormat::JFormat => {
@@ -207,7 +386,7 @@ impl Display for o3oInst {
                     f,
                     "{} {}, {:#x}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
                     self.0.imm.unwrap()
                 )
             }
@@ -219,8 +398,8 @@ impl Display for o3oInst {
                     f,
                     "{} {}, {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
                     self.0.imm.unwrap()
                 )
             }
@@ -228,28 +407,35 @@ impl Display for o3oInst {
                 OpcodeKind::C(COpcode::JR) => {
                     write!(
                         f,
-                        "{} zero, 0({})",
+                        "{} {}, 0({})",
                         self.0.opc,
-                        reg2str(self.0.rs1.unwrap()),
+                        self.1.reg_name(0),
+                        self.1.reg_name(self.0.rs1.unwrap()),
                     )
                 }
                 OpcodeKind::C(COpcode::JALR) => {
-                    write!(f, "{} ra, 0({})", self.0.opc, reg2str(self.0.rs1.unwrap()),)
+                    write!(
+                        f,
+                        "{} {}, 0({})",
+                        self.0.opc,
+                        self.1.reg_name(1),
+                        self.1.reg_name(self.0.rs1.unwrap()),
+                    )
                 }
                 OpcodeKind::C(COpcode::MV) => write!(
                     f,
                     "{} {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rs2.unwrap())
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rs2.unwrap())
                 ),
                 OpcodeKind::C(COpcode::ADD) => write!(
                     f,
                     "{} {}, {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rs2.unwrap())
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rs2.unwrap())
                 ),
                 _ => unreachable!(),
             },
@@ -258,9 +444,9 @@ impl Display for o3oInst {
                     f,
                     "{} {}, {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rd.unwrap()),
-                    reg2str(self.0.rs2.unwrap())
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rs2.unwrap())
                 )
             }
             InstFormat::CbFormat => {
@@ -268,7 +454,7 @@ impl Display for o3oInst {
                     f,
                     "{} {}, {}",
                     self.0.opc,
-                    self.0.rs1.unwrap(),
+                    self.1.reg_name(self.0.rs1.unwrap()),
                     self.0.imm.unwrap(),
                 )
             }
@@ -277,9 +463,9 @@ impl Display for o3oInst {
                     f,
                     "{} {}, {:#x}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
                     self.0.rs2.unwrap(),
-                    reg2str(self.0.rs1.unwrap()),
+                    self.1.reg_name(self.0.rs1.unwrap()),
                 )
             }
             InstFormat::CsrUiFormat => {
@@ -287,19 +473,19 @@ impl Display for o3oInst {
                     f,
                     "{} {}, {}, {}",
                     self.0.opc,
-                    reg2str(self.0.rd.unwrap()),
+                    self.1.reg_name(self.0.rd.unwrap()),
                     self.0.rs2.unwrap(),
                     self.0.imm.unwrap(),
                 )
             }
             InstFormat::OnlyRd => {
-                write!(f, "{} {}", self.0.opc, reg2str(self.0.rd.unwrap()),)
+                write!(f, "{} {}", self.0.opc, self.1.reg_name(self.0.rd.unwrap()),)
             }
             InstFormat::OnlyRs1 => {
-                write!(f, "{} {}", self.0.opc, reg2str(self.0.rs1.unwrap()),)
+                write!(f, "{} {}", self.0.opc, self.1.reg_name(self.0.rs1.unwrap()),)
             }
             InstFormat::OnlyRs2 => {
-                write!(f, "{} {}", self.0.opc, reg2str(self.0.rs2.unwrap()),)
+                write!(f, "{} {}", self.0.opc, self.1.reg_name(self.0.rs2.unwrap()),)
             }
             InstFormat::NoOperand => match self.0.opc {
                 OpcodeKind::BaseI(BaseIOpcode::ECALL | BaseIOpcode::EBREAK)
@@ -314,21 +500,63 @@ impl Display for o3oInst {
     }
 }
 
-/// Convert register number to string.
-fn reg2str(rd_value: usize) -> String {
-    format!("x{rd_value}")
+/// ABI names of x0-x31, in order.
+pub const ABI_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// Value of the signal `key`, or `None` if it's missing or has x/z bits.
+pub fn get_known(key: &str, snapshots: &Snapshots) -> Option<usize> {
+    snapshots
+        .get_var(key)
+        .filter(|x| !x.is_unknown())
+        .map(VerilogValue::as_usize)
+}
+
+/// `base[0]`, `base[1]`, ... for as many as there are.
+pub fn indexed(base: &str, snapshots: &Snapshots) -> Vec<String> {
+    let mut entries = Vec::new();
+    while snapshots
+        .get_scope(&format!("{base}[{}]", entries.len()))
+        .is_some()
+    {
+        entries.push(format!("{base}[{}]", entries.len()));
+    }
+    entries
+}
+
+/// Rows a list is scrolled with <J/K> away from the row it follows the cursor to.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Scroll(isize);
+
+impl Scroll {
+    /// Moves with <J/K>. Returns true if the key was one of them.
+    pub fn on_key_event(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char('J') => self.0 += 1,
+            KeyCode::Char('K') => self.0 -= 1,
+            _ => return false,
+        }
+        true
+    }
+
+    /// First row to show of `len` when `num_rows` fit and it'd be `synced` without scrolling,
+    /// kept from scrolling past either end.
+    pub fn first(&self, synced: usize, len: usize, num_rows: usize) -> usize {
+        synced
+            .saturating_add_signed(self.0)
+            .min(len.saturating_sub(num_rows))
+    }
 }
 
 pub fn parse_inst(base: &str, snapshots: &Snapshots) -> String {
-    let inst_bits = snapshots
-        .get_var(&format!("{base}.inst"))
-        .unwrap()
-        .as_usize();
-    let Ok(inst) = (inst_bits as u32).decode(Isa::Rv32) else {
-        return String::from("<invalid>");
-    };
-    let inst = o3oInst(inst);
-    format!("{inst}")
+    let inst_bits = snapshots.get_var_or_missing(&format!("{base}.inst"));
+    if inst_bits.is_missing() {
+        return inst_bits.to_string();
+    }
+    snapshots.context.disasm(inst_bits.as_usize())
 }
 
 pub fn parse_mem_command(val: &VerilogValue) -> &'static str {
@@ -397,18 +625,24 @@ pub const LEFT_BORDER_SET: symbols::border::Set = symbols::border::Set {
 };
 
 pub fn parse_opinfo(base: &str, snapshots: &Snapshots) -> String {
-    let pc = snapshots.get_var(&format!("{base}.PC")).unwrap().as_usize();
+    let pc = snapshots.get_var_or_missing(&format!("{base}.PC"));
+    let inst_bits = snapshots.get_var_or_missing(&format!("{base}.inst.inst"));
+    if pc.is_missing() || inst_bits.is_missing() {
+        return VerilogValue::Missing.to_string();
+    }
 
-    let inst_bits = snapshots
-        .get_var(&format!("{base}.inst.inst"))
-        .unwrap()
-        .as_usize();
-    let Ok(inst) = (inst_bits as u32).decode(Isa::Rv32) else {
+    let pc = pc.as_usize();
+    let inst_bits = inst_bits.as_usize();
+    let context = &snapshots.context;
+    let Some(inst) = context.isa.decode(inst_bits) else {
         return format!("{pc}: <invalid>");
     };
-    let inst = o3oInst(inst);
+    let inst = o3oInst(inst, context);
 
-    format!("{pc:x}: {inst}")
+    match context.symbolize(pc) {
+        Some(symbol) => format!("{pc:x} <{symbol}>: {inst}"),
+        None => format!("{pc:x}: {inst}"),
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -454,12 +688,10 @@ impl Columns {
         for col in self.columns.iter() {
             if let Some(key) = col.key {
                 let full_key = format!("{base}.{key}");
-                if let Some(value) = snapshots.get_var(&full_key) {
-                    let string = value.format(&col.display_type);
-                    cells.push(Cell::new(string));
-                } else {
-                    cells.push(Cell::new("<missing>"));
-                }
+                let string = snapshots
+                    .get_var_or_missing(&full_key)
+                    .format(&col.display_type, &snapshots.context);
+                cells.push(Cell::new(string));
             } else {
                 let string = match col.name {
                     "op" => {
@@ -468,15 +700,17 @@ impl Columns {
                     }
                     "#" => num.to_string(),
                     "addr" => {
-                        let tag = snapshots.get_var(&format!("{base}.addr.tag")).unwrap();
-                        let set_num = snapshots.get_var(&format!("{base}.addr.set_num")).unwrap();
-                        let offset = snapshots
-                            .get_var(&format!("{base}.addr.block_offset"))
-                            .unwrap();
+                        let tag = snapshots.get_var_or_missing(&format!("{base}.addr.tag"));
+                        let offset =
+                            snapshots.get_var_or_missing(&format!("{base}.addr.block_offset"));
 
-                        let addr = &(tag + set_num) + offset;
+                        // fully associative caches don't have a set number
+                        let addr = match snapshots.get_var(&format!("{base}.addr.set_num")) {
+                            Some(set_num) => &(tag + set_num) + offset,
+                            None => tag + offset,
+                        };
 
-                        addr.as_hex()
+                        addr.as_addr(&snapshots.context)
                     }
                     _ => {
                         panic!("unrecognized unkeyed column {}", col.name)
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use simsearch::{SearchOptions, SimSearch};
 use vcd::{Header, IdCode, Scope, ScopeItem};
@@ -6,6 +6,9 @@ use vcd::{Header, IdCode, Scope, ScopeItem};
 pub struct VarIndex {
     pub vars: HashMap<String, IdCode>,
     pub engine: SimSearch<String>,
+    /// scopes that can be shown as tables: arrays of scopes, like `rob.entries` for
+    /// `rob.entries[0]`, and the scopes holding them
+    pub tables: SimSearch<String>,
 }
 
 impl VarIndex {
@@ -25,6 +28,8 @@ impl VarIndex {
         }
 
         let mut engine = SimSearch::new_with(SearchOptions::new().levenshtein(true).threshold(0.7));
+        let mut tables = SimSearch::new_with(SearchOptions::new().levenshtein(true).threshold(0.7));
+        let mut seen_tables = HashSet::new();
 
         while let Some((prefix, next)) = to_search.pop() {
             for scope_item in next.items {
@@ -32,6 +37,13 @@ impl VarIndex {
                     ScopeItem::Scope(scope) => {
                         let name = prefix.clone() + "." + &scope.identifier;
                         engine.insert(name.clone(), &name);
+                        if let Some((array, _)) = scope.identifier.split_once('[') {
+                            for table in [prefix.clone(), prefix.clone() + "." + array] {
+                                if seen_tables.insert(table.clone()) {
+                                    tables.insert(table.clone(), &table);
+                                }
+                            }
+                        }
                         // trace_dbg!(&name);
                         to_search.push((name, scope.clone()));
                     }
@@ -45,7 +57,11 @@ impl VarIndex {
                 }
             }
         }
-        Self { vars, engine }
+        Self {
+            vars,
+            engine,
+            tables,
+        }
     }
 
     pub fn get(&self, query: &str) -> Option<IdCode> {